@@ -0,0 +1,77 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering}
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use strawberride::{Element, Entity, Level, Map};
+
+/// Counts allocations, so the benchmark can show how many strings decoding makes.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// A big map, with lots of entities repeating the same names and attribute keys.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+fn map_bytes() -> Vec<u8> {
+    let levels = (0 .. 50).map(|i| {
+        let mut level = Level::new(format!("room-{i}"), 640, 360).unwrap();
+        level.solids.map_cells(|_| '1');
+        level.entities = (0 .. 200).map(|id| Entity {
+            name: "spikesUp".into(),
+            id,
+            position: (id as f32 * 8.0, 168.0),
+            nodes: vec![(id as f32 * 8.0, 160.0)],
+            ..Default::default()
+        }).collect();
+        level
+    }).collect();
+    let mut bytes = Vec::new();
+    Map { package: "bench".into(), levels, ..Default::default() }.store(&mut bytes, true).unwrap();
+    bytes
+}
+
+fn load(c: &mut Criterion) {
+    let bytes = map_bytes();
+
+    let load_map = || {
+        black_box(Map::load(&mut black_box(bytes.as_slice()), true).unwrap());
+    };
+    let load_elements = || {
+        black_box(Element::from_map_bytes(black_box(&bytes), true).unwrap());
+    };
+    println!(
+        "allocations per map: Map::load = {}, Element::from_map_bytes = {}",
+        allocations(load_map), allocations(load_elements)
+    );
+
+    let mut group = c.benchmark_group("map load");
+    group.sample_size(20);
+    group.bench_function("Map::load", |b| b.iter(load_map));
+    group.bench_function("Element::from_map_bytes", |b| b.iter(load_elements));
+    group.finish();
+}
+
+criterion_group!(benches, load);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering}
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use strawberride::{Entity, Level, Map};
+
+/// Counts reallocations, so the benchmark can show how often buffers had to grow.
+struct CountingAlloc;
+
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn reallocations(f: impl FnOnce()) -> usize {
+    let before = REALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    REALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// A big map, with lots of tiles and entities to encode.
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+fn map() -> Map {
+    let levels = (0 .. 50).map(|i| {
+        let mut level = Level::new(format!("room-{i}"), 640, 360).unwrap();
+        level.solids.map_cells(|_| '1');
+        level.fg_tiles.map_cells(|_| 12);
+        level.entities = (0 .. 200).map(|id| Entity {
+            name: "spikesUp".into(),
+            id,
+            position: (id as f32 * 8.0, 168.0),
+            ..Default::default()
+        }).collect();
+        level
+    }).collect();
+    Map { package: "bench".into(), levels, ..Default::default() }
+}
+
+fn store(c: &mut Criterion) {
+    let map = map();
+    let mut size = Vec::new();
+    map.clone().store(&mut size, true).unwrap();
+    let size = size.len();
+
+    // The output is sized up front, so any reallocations come from encoding
+    let store = || {
+        let mut out = Vec::with_capacity(size);
+        black_box(map.clone()).store(&mut out, true).unwrap();
+        black_box(out);
+    };
+    // This has no buffer of its own, so it shows how many reallocations happen outside of the buffer in `store`
+    let store_streaming = || {
+        let mut out = Vec::with_capacity(size);
+        black_box(map.clone()).store_streaming(&mut out, true).unwrap();
+        black_box(out);
+    };
+    println!(
+        "reallocations per map: store = {}, store_streaming = {}",
+        reallocations(store), reallocations(store_streaming)
+    );
+
+    let mut group = c.benchmark_group("map store");
+    group.sample_size(20);
+    group.bench_function("store", |b| b.iter(store));
+    group.bench_function("store_streaming", |b| b.iter(store_streaming));
+    group.finish();
+}
+
+criterion_group!(benches, store);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use strawberride::{Level, Map, Tilemap};
+
+// Enough big rooms for splitting the work up to pay off
+const ROOMS: usize = 32;
+
+fn map() -> Map {
+    let levels = (0 .. ROOMS).map(|i| Level::new(format!("room-{i}"), 2560, 1440).unwrap()).collect();
+    Map { levels, ..Default::default() }
+}
+
+/// Something a bit more expensive than copying cells around, like a batch retile would be.
+fn retile(level: &mut Level) {
+    level.fg_tiles.map_cells(|cell| (0 .. 8).fold(cell, |acc, i| acc.wrapping_mul(31).wrapping_add(i)));
+    level.solids.map_cells(|cell| if cell == '0' { '1' } else { '0' });
+}
+
+fn parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(20);
+
+    let mut tilemap = Tilemap::<i32>::new(2560, 1440).unwrap();
+    let f = |cell: i32| (0 .. 8).fold(cell, |acc, i| acc.wrapping_mul(31).wrapping_add(i));
+    group.bench_function("Tilemap::map_cells", |b| b.iter(|| black_box(&mut tilemap).map_cells(f)));
+    group.bench_function("Tilemap::par_map_cells", |b| b.iter(|| black_box(&mut tilemap).par_map_cells(f)));
+
+    let mut map = map();
+    group.bench_function("Map::for_each_level_mut", |b| b.iter(|| black_box(&mut map).for_each_level_mut(retile)));
+    group.bench_function("Map::par_for_each_level_mut", |b| b.iter(|| black_box(&mut map).par_for_each_level_mut(retile)));
+    group.finish();
+}
+
+criterion_group!(benches, parallel);
+criterion_main!(benches);
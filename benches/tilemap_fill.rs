@@ -0,0 +1,27 @@
+use std::{hint::black_box, iter};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use strawberride::Tilemap;
+
+// A large room, in tiles
+const WIDTH: usize = 320;
+const HEIGHT: usize = 180;
+
+fn fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tilemap fill");
+    // This is what `Tilemap::new` used to do
+    #[allow(clippy::manual_repeat_n)]
+    group.bench_function("iter::repeat().take().collect()", |b| b.iter(||
+        black_box(iter::repeat(black_box(-1_i32)).take(WIDTH * HEIGHT).collect::<Vec<_>>())
+    ));
+    group.bench_function("vec![]", |b| b.iter(||
+        black_box(vec![black_box(-1_i32); WIDTH * HEIGHT])
+    ));
+    group.bench_function("Tilemap::new", |b| b.iter(||
+        black_box(Tilemap::<i32>::new(black_box(WIDTH), black_box(HEIGHT)))
+    ));
+    group.finish();
+}
+
+criterion_group!(benches, fill);
+criterion_main!(benches);
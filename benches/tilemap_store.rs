@@ -0,0 +1,108 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering}
+};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use strawberride::{Level, Map, Tilemap};
+
+/// Counts allocations, so the benchmark can show how many each approach makes.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// A room's worth of layers, with some tiles filled in so rows aren't trivially empty.
+fn layers() -> Vec<Tilemap<char>> {
+    (0 .. 5).map(|layer| {
+        let mut tilemap = Tilemap::new(160, 90).unwrap();
+        for y in 0 .. 90 {
+            for x in (0 .. 160).filter(|x| (x + y + layer) % 3 == 0) {
+                tilemap[(x, y)] = '1';
+            }
+        }
+        tilemap
+    }).collect()
+}
+
+fn store(c: &mut Criterion) {
+    let layers = layers();
+
+    let fresh = || for layer in &layers {
+        black_box(layer.store());
+    };
+    let mut buf = String::new();
+    let mut reused = || for layer in &layers {
+        buf.clear();
+        layer.store_into(&mut buf);
+        black_box(&buf);
+    };
+    // Warm the reused buffer up, like it would be after the first room
+    reused();
+    println!(
+        "allocations per room: store = {}, store_into with a reused buffer = {}",
+        allocations(fresh), allocations(&mut reused)
+    );
+
+    // `store` builds an element with its own string for every tilemap,
+    // while `store_streaming` stores them into one reused buffer as it writes them
+    let map = Map {
+        levels: (0 .. 50).map(|i| {
+            let mut level = Level::new(format!("room-{i}"), 320, 184).unwrap();
+            level.solids.map_cells(|_| '1');
+            level.fg_tiles.map_cells(|_| 12);
+            level
+        }).collect(),
+        ..Default::default()
+    };
+    let mut out = Vec::new();
+    map.clone().store(&mut out, true).unwrap();
+    let size = out.len();
+    let map_store = || {
+        let mut out = Vec::with_capacity(size);
+        black_box(map.clone()).store(&mut out, true).unwrap();
+    };
+    let map_store_streaming = || {
+        let mut out = Vec::with_capacity(size);
+        black_box(map.clone()).store_streaming(&mut out, true).unwrap();
+    };
+    println!(
+        "allocations per 50 room map: store = {}, store_streaming = {}",
+        allocations(map_store), allocations(map_store_streaming)
+    );
+
+    let mut group = c.benchmark_group("tilemap store");
+    group.bench_function("store", |b| b.iter(fresh));
+    group.bench_function("store_into (reused buffer)", |b| b.iter(&mut reused));
+    group.bench_function("map store", |b| b.iter(map_store));
+    group.bench_function("map store_streaming", |b| b.iter(map_store_streaming));
+    group.finish();
+}
+
+criterion_group!(benches, store);
+criterion_main!(benches);
@@ -1,14 +1,15 @@
-use std::{borrow::Cow, collections::HashMap, fmt::Write, io};
+use std::{borrow::Cow, collections::HashMap, fmt::Write, hash::Hash, io, sync::Arc};
 
 use byteorder::{LittleEndian, ReadBytesExt as _, WriteBytesExt};
 use indexmap::IndexSet;
 use itertools::Itertools as _;
 use indent_write::fmt::IndentWriter;
 
-use crate::{ext::{ReadExt as _, WriteExt as _}, LoadError};
+use crate::{ext::{self, ReadExt as _, WriteExt as _}, LoadError, LoadOptions, StoreError, Tilemap};
 
 /// A value that can appear in the attributes of an element.
 #[derive(Clone, PartialEq, Debug)]
+#[non_exhaustive]
 pub enum Value {
     /// A boolean value.
     Boolean(bool),
@@ -20,6 +21,37 @@ pub enum Value {
     String(String),
     /// A string, specifically written in run-length encoding.
     RleString(String),
+    /// A value with a type tag this library doesn't know about, holding the tag and the raw bytes of the value.
+    /// 
+    /// These only show up when loading with [`LoadOptions::lenient_values`](crate::LoadOptions::lenient_values),
+    /// and are stored back exactly as they were read. Storing one with the tag of a known type (0 to 7) fails,
+    /// since it would be read back as that type instead.
+    Unknown(u8, Vec<u8>),
+}
+
+/// Arbitrary cutoff for strings that are 
+/// too long to likely be repeated
+/// e.g. tilemaps
+pub(crate) const LOOKUP_CUTOFF: usize = 64;
+
+/// Formats a color as a hex string, the way Celeste stores colors.
+pub(crate) fn color_to_hex(color: [u8; 4]) -> String {
+    color.into_iter()
+        .map(|v| format!("{v:02x}"))
+        .join("")
+}
+
+/// The kind of a [`Value`], without its contents.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ValueKind {
+    Boolean,
+    Integer,
+    Float,
+    String,
+    RleString,
+    Unknown
 }
 
 impl std::fmt::Display for Value {
@@ -31,12 +63,77 @@ impl std::fmt::Display for Value {
             Self::String(arg0)
                 | Self::RleString(arg0)
                 => write!(f, "{arg0:?}"),
+            Self::Unknown(tag, bytes) => write!(f, "unknown({tag}:{})", bytes.iter().map(|b| format!("{b:02x}")).join("")),
         }
     }
 }
 
 impl Value {
-    pub(crate) fn decode(stream: &mut dyn io::Read, lookup: &Vec<String>) -> Result<Self, LoadError> {
+    /// Gets the kind of this value.
+    #[must_use]
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Boolean(_) => ValueKind::Boolean,
+            Value::Integer(_) => ValueKind::Integer,
+            Value::Float(_) => ValueKind::Float,
+            Value::String(_) => ValueKind::String,
+            Value::RleString(_) => ValueKind::RleString,
+            Value::Unknown(..) => ValueKind::Unknown
+        }
+    }
+
+    /// Converts this value into a value of the given kind, if it can be.
+    /// 
+    /// Strings are parsed (booleans as `"true"` or `"false"`, ignoring case),
+    /// numbers convert between each other, and anything can become a string.
+    /// Returns [`None`] if the conversion doesn't make sense, like `"abc"` into an integer.
+    /// Unknown values can't be converted to or from anything.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn coerce(&self, kind: ValueKind) -> Option<Value> {
+        Some( match (self, kind) {
+            (Value::Unknown(..), _) | (_, ValueKind::Unknown)
+                | (Value::Boolean(_), ValueKind::Float) | (Value::Float(_), ValueKind::Boolean) => return None,
+            (Value::Boolean(b), ValueKind::Boolean) => Value::Boolean(*b),
+            (Value::Integer(i), ValueKind::Boolean) => Value::Boolean(*i != 0),
+            (Value::String(s) | Value::RleString(s), ValueKind::Boolean) =>
+                if s.trim().eq_ignore_ascii_case("true") { Value::Boolean(true) }
+                else if s.trim().eq_ignore_ascii_case("false") { Value::Boolean(false) }
+                else { return None },
+            (Value::Boolean(b), ValueKind::Integer) => Value::Integer(i32::from(*b)),
+            (Value::Integer(i), ValueKind::Integer) => Value::Integer(*i),
+            (Value::Float(f), ValueKind::Integer) => Value::Integer(*f as i32),
+            (Value::String(s) | Value::RleString(s), ValueKind::Integer) => Value::Integer(s.trim().parse().ok()?),
+            (Value::Integer(i), ValueKind::Float) => Value::Float(*i as f32),
+            (Value::Float(f), ValueKind::Float) => Value::Float(*f),
+            (Value::String(s) | Value::RleString(s), ValueKind::Float) => Value::Float(s.trim().parse().ok()?),
+            (Value::String(s) | Value::RleString(s), ValueKind::String) => Value::String(s.clone()),
+            (Value::String(s) | Value::RleString(s), ValueKind::RleString) => Value::RleString(s.clone()),
+            (other, ValueKind::String) => Value::String(other.to_string()),
+            (other, ValueKind::RleString) => Value::RleString(other.to_string())
+        } )
+    }
+
+    /// Creates a string value holding a color as a hex string, the way Celeste stores colors.
+    #[must_use]
+    pub fn from_color(color: [u8; 4]) -> Value {
+        Value::String(color_to_hex(color))
+    }
+
+    /// Compares two values by content, treating [`Value::String`] and [`Value::RleString`] as equal
+    /// if they hold the same text.
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (
+                Value::String(a) | Value::RleString(a),
+                Value::String(b) | Value::RleString(b)
+            ) => a == b,
+            (a, b) => a == b
+        }
+    }
+
+    pub(crate) fn decode(stream: &mut dyn io::Read, lookup: &[Arc<str>], options: LoadOptions) -> Result<Self, LoadError> {
         Ok( match stream.read_u8()? {
             0 => (stream.read_u8()? > 0).into(), // Boolean value
             1 => (stream.read_u8()? as i32).into(),
@@ -44,13 +141,92 @@ impl Value {
             3 => stream.read_i32::<LittleEndian>()?.into(),
             4 => stream.read_f32::<LittleEndian>()?.into(),
             5 => stream.lookup_string(lookup)?.to_string().into(),
-            6 => stream.read_string()?.into(),
-            7 => Self::RleString(stream.read_rle_string()?),
+            6 => stream.read_string(options.lossy_utf8)?.into(),
+            7 => Self::RleString(stream.read_rle_string(options.lossy_utf8)?),
+            unknown if options.lenient_values => {
+                let length = stream.read_variable_length_int()? as u64;
+                let mut bytes = Vec::new();
+                io::Read::read_to_end(&mut io::Read::take(&mut *stream, length), &mut bytes)?;
+                if (bytes.len() as u64) < length {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+                Self::Unknown(unknown, bytes)
+            },
             invalid => Err(LoadError::InvalidValueType(invalid))?
         })
     }
 
-    pub(crate) fn encode(self, stream: &mut dyn io::Write, lookup: &mut IndexSet<String>) -> io::Result<()> {
+    /// Skips over a value without decoding it.
+    pub(crate) fn skip(stream: &mut dyn io::Read) -> Result<(), LoadError> {
+        let length = match stream.read_u8()? {
+            0 | 1 => 1,
+            2 | 5 => 2,
+            3 | 4 => 4,
+            6 => stream.read_variable_length_int()? as u64,
+            7 => u64::from(stream.read_u16::<LittleEndian>()?),
+            invalid => return Err(LoadError::InvalidValueType(invalid))
+        };
+        let skipped = io::copy(&mut io::Read::take(&mut *stream, length), &mut io::sink())?;
+        if skipped < length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(())
+    }
+
+    /// Gets the type tag that this value is written with when encoded.
+    /// 
+    /// Integers use the smallest type that fits them, and short strings are written as lookup table indices (type 5),
+    /// unless the lookup table is full when storing, in which case they're written inline (type 6).
+    #[must_use]
+    pub fn wire_type(&self) -> u8 {
+        match self {
+            Value::Boolean(_) => 0,
+            Value::Integer(int) =>
+                if u8::try_from(*int).is_ok() { 1 }
+                else if i16::try_from(*int).is_ok() { 2 }
+                else { 3 },
+            Value::Float(_) => 4,
+            Value::String(str) => if str.len() >= LOOKUP_CUTOFF { 6 } else { 5 },
+            Value::RleString(_) => 7,
+            Value::Unknown(tag, _) => *tag
+        }
+    }
+
+    /// Gets the number of bytes this value takes up when encoded, including its type tag.
+    /// 
+    /// Strings short enough to go in the lookup table are counted as the size of their index,
+    /// not the size of the string itself.
+    #[must_use]
+    pub fn encoded_size(&self) -> usize {
+        1 + match self {
+            Value::Boolean(_) => 1,
+            Value::Integer(int) =>
+                if u8::try_from(*int).is_ok() { 1 }
+                else if i16::try_from(*int).is_ok() { 2 }
+                else { 4 },
+            Value::Float(_) => 4,
+            Value::String(str) => string_payload_size(str, false),
+            Value::RleString(str) => string_payload_size(str, true),
+            Value::Unknown(_, bytes) => ext::variable_length_int_size(bytes.len()) + bytes.len()
+        }
+    }
+
+    /// Adds the string this value would put in the lookup table when encoded, if any.
+    pub(crate) fn collect_string(&self, lookup: &mut IndexSet<Arc<str>>) {
+        if let Value::String(str) = self {
+            Value::collect_str(str, lookup);
+        }
+    }
+
+    /// Adds a string to the lookup table if a [`Value::String`] holding it would be looked up.
+    pub(crate) fn collect_str(str: &str, lookup: &mut IndexSet<Arc<str>>) {
+        if str.len() < LOOKUP_CUTOFF && !lookup.contains(str) {
+            lookup.insert(str.into());
+        }
+    }
+
+    pub(crate) fn encode(self, stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>) -> io::Result<()> {
         match self {
             Value::Boolean(bool) => stream.write_all(&[0, bool as u8]),
             Value::Integer(int) => 
@@ -67,31 +243,70 @@ impl Value {
                 stream.write_u8(4)?;
                 stream.write_f32::<LittleEndian>(float)
             },
-            Value::String(str) => {
-                // Arbitrary cutoff for strings that are 
-                // too long to likely be repeated
-                // e.g. tilemaps
-                if str.len() >= 64 {
-                    stream.write_u8(6)?;
-                    return stream.write_string(&str);
-                }
-                let (index, _) = lookup.insert_full(str);
-                if index > u16::MAX as usize {
-                    // Since we're out of space, write normally
-                    stream.write_u8(6)?;
-                    return stream.write_string(
-                        lookup.get_index(index).unwrap()
-                    );
+            Value::String(str) => Value::encode_str(&str, false, stream, lookup),
+            Value::RleString(str) => Value::encode_str(&str, true, stream, lookup),
+            Value::Unknown(tag, bytes) => {
+                if tag <= 7 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("unknown value has the tag {tag}, which belongs to a known type")
+                    ));
                 }
-                stream.write_u8(5)?;
-                stream.write_u16::<LittleEndian>(index as u16)
-            },
-            Value::RleString(str) => {
-                stream.write_u8(7)?;
-                stream.write_rle_string(&str)
+                stream.write_u8(tag)?;
+                stream.write_variable_length_int(bytes.len())?;
+                stream.write_all(&bytes)
             }
         }
     }
+
+    /// Encodes a string the same way as a [`Value::String`] or [`Value::RleString`] holding it, without needing to own it.
+    pub(crate) fn encode_str(str: &str, rle: bool, stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>) -> io::Result<()> {
+        if rle {
+            stream.write_u8(7)?;
+            return stream.write_rle_string(str);
+        }
+        if str.len() >= LOOKUP_CUTOFF {
+            stream.write_u8(6)?;
+            return stream.write_string(str);
+        }
+        // Most strings are already in the table, so this avoids making a new one for them
+        let index = lookup.get_index_of(str)
+            .unwrap_or_else(|| lookup.insert_full(str.into()).0);
+        if index > u16::MAX as usize {
+            // Since we're out of space, write normally
+            stream.write_u8(6)?;
+            return stream.write_string(
+                lookup.get_index(index).unwrap()
+            );
+        }
+        stream.write_u8(5)?;
+        stream.write_u16::<LittleEndian>(index as u16)
+    }
+}
+
+/// Gets the size of an encoded string value without its type tag, like [`Value::encoded_size`],
+/// for when the string isn't owned by a [`Value`].
+pub(crate) fn string_payload_size(str: &str, rle: bool) -> usize {
+    if rle { 2 + ext::rle_string_size(str) }
+    else if str.len() >= LOOKUP_CUTOFF { ext::variable_length_int_size(str.len()) + str.len() }
+    else { 2 }
+}
+
+/// Writes the index of a name in the lookup table, adding it if it isn't there yet.
+fn write_lookup_index(
+    stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>, name: impl AsRef<str> + Into<Arc<str>>
+) -> io::Result<()> {
+    let index = lookup.get_index_of(name.as_ref())
+        .unwrap_or_else(|| lookup.insert_full(name.into()).0);
+    let index = u16::try_from(index)
+        .map_err(|_| io::Error::other("cannot store more than 65535 unique strings"))?;
+    stream.write_u16::<LittleEndian>(index)
+}
+
+impl From<[u8; 4]> for Value {
+    fn from(value: [u8; 4]) -> Value {
+        Value::from_color(value)
+    }
 }
 
 macro_rules! value_from_type_impl {
@@ -112,34 +327,114 @@ value_from_type_impl! {
 }
 
 /// An element of a map.
+/// 
+/// Names and attribute keys are shared [`Arc<str>`]s, since the map format stores each one
+/// once in a lookup table, and the same few names repeat thousands of times in a big map.
+/// Decoding hands out clones of the lookup table's strings instead of allocating a new one for each element.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Element {
     /// The element's name.
-    pub name: String,
+    pub name: Arc<str>,
     /// The element's attributes.
-    pub attributes: HashMap<String, Value>,
+    pub attributes: HashMap<Arc<str>, Value>,
     /// The element's child elements.
     pub children: Vec<Element>
 }
 
+/// Compares two attribute maps with [`Value::semantic_eq`].
+pub(crate) fn attributes_semantic_eq<K: Eq + Hash>(a: &HashMap<K, Value>, b: &HashMap<K, Value>) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(key, value)|
+            b.get(key).is_some_and(|other| value.semantic_eq(other))
+        )
+}
+
+/// Compares two lists of elements with [`Element::semantic_eq`], ignoring their order.
+pub(crate) fn children_semantic_eq(a: &[Element], b: &[Element]) -> bool {
+    a.len() == b.len()
+        && a.iter().sorted_by_key(|el| &el.name)
+            .zip(b.iter().sorted_by_key(|el| &el.name))
+            .all(|(a, b)| a.semantic_eq(b))
+}
+
 impl Element {
-    pub(crate) fn decode(stream: &mut dyn io::Read, lookup: &Vec<String>) -> Result<Element, LoadError> {
-        let name = stream.lookup_string(lookup)?.to_string();
+    /// Compares two elements by content.
+    /// 
+    /// Attribute values are compared with [`Value::semantic_eq`],
+    /// and children are compared regardless of their order.
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Element) -> bool {
+        self.name == other.name
+            && attributes_semantic_eq(&self.attributes, &other.attributes)
+            && children_semantic_eq(&self.children, &other.children)
+    }
+
+    /// Gets the inner text of this element, if it has any.
+    /// 
+    /// Returns [`None`] if there is no `innerText` attribute, or if it isn't a string.
+    #[must_use]
+    pub fn inner_text(&self) -> Option<&str> {
+        match self.attributes.get("innerText")? {
+            Value::String(text) | Value::RleString(text) => Some(text),
+            _ => None
+        }
+    }
+
+    /// Iterates over the element's attributes, sorted by name.
+    /// 
+    /// This is the order attributes are written in when storing and displaying,
+    /// so that the output is the same every time.
+    pub fn sorted_attributes(&self) -> impl Iterator<Item = (&Arc<str>, &Value)> {
+        self.attributes.iter().sorted_unstable_by(|(a, _), (b, _)| a.cmp(b))
+    }
+
+    /// Reads this element's inner text as a character tilemap, the way `bg` and `solids` are loaded.
+    /// 
+    /// This is useful for tile layers this library doesn't know about, which end up in [`Level::extra_children`](crate::Level::extra_children).
+    /// Tiles outside of the given size are dropped, and missing tiles are left empty.
+    /// Returns [`None`] if there's no inner text, or if the size is too large to fit in memory.
+    #[must_use]
+    pub fn as_tilemap_char(&self, width: usize, height: usize) -> Option<Tilemap<char>> {
+        Tilemap::<char>::load(self.inner_text()?, width, height)
+    }
+
+    /// Reads this element's inner text as an integer tilemap, the way `bgtiles`, `fgtiles` and `objtiles` are loaded.
+    /// 
+    /// See [`Element::as_tilemap_char`].
+    #[must_use]
+    pub fn as_tilemap_i32(&self, width: usize, height: usize) -> Option<Tilemap<i32>> {
+        Tilemap::<i32>::load(self.inner_text()?, width, height)
+    }
+
+    /// Decodes an element and all of its children.
+    /// 
+    /// `on_element` is called after each element (including this one) is decoded.
+    pub(crate) fn decode(
+        stream: &mut dyn io::Read, lookup: &[Arc<str>], options: LoadOptions, on_element: &mut dyn FnMut()
+    ) -> Result<Element, LoadError> {
+        let name = Arc::clone(stream.lookup_string(lookup)?);
+        Self::decode_body(stream, lookup, name, options, on_element)
+    }
 
+    /// Decodes the rest of an element whose name has already been read. See [`Element::decode`].
+    pub(crate) fn decode_body(
+        stream: &mut dyn io::Read, lookup: &[Arc<str>], name: Arc<str>, options: LoadOptions, on_element: &mut dyn FnMut()
+    ) -> Result<Element, LoadError> {
         let attr_count = stream.read_u8()?;
         let mut attributes = HashMap::with_capacity(attr_count as usize);
         (0..attr_count).map(|_| {
-            let key = stream.lookup_string(lookup)?.to_string();
-            let value = Value::decode(stream, lookup)?;
+            let key = Arc::clone(stream.lookup_string(lookup)?);
+            let value = Value::decode(stream, lookup, options)?;
 
             Ok::<_, LoadError>((key, value))
         }).process_results(|iter| attributes.extend(iter))?;
 
         let child_count = stream.read_u16::<LittleEndian>()?;
         let children = (0 .. child_count)
-            .map(|_| Element::decode(stream, lookup))
+            .map(|_| Element::decode(stream, lookup, options, on_element))
             .collect::<Result<Vec<_>, _>>()?;
 
+        on_element();
         Ok(Element {
             name,
             attributes,
@@ -147,22 +442,121 @@ impl Element {
         })
     }
 
-    pub(crate) fn encode(self, stream: &mut dyn io::Write, lookup: &mut IndexSet<String>) -> io::Result<()> {
-        let name_index = u16::try_from(lookup.insert_full(self.name).0)
-            .map_err(|_| io::Error::other("cannot store more than 65535 unique strings"))?;
-        stream.write_u16::<LittleEndian>(name_index)?;
+    /// Decodes an element like [`Element::decode`], but keeps what it can if decoding fails partway through.
+    /// 
+    /// Decoding stops at the first error, which is returned alongside the element.
+    /// Elements that were only partly decoded are kept with the children that were complete, 
+    /// but only down to `keep_partial` levels below this one. Deeper partial elements are dropped.
+    /// The element itself is [`None`] only if its name or attributes couldn't be read.
+    pub(crate) fn decode_partial(
+        stream: &mut dyn io::Read, lookup: &[Arc<str>], options: LoadOptions, keep_partial: usize
+    ) -> (Option<Element>, Option<LoadError>) {
+        let mut read_head = || {
+            let name = Arc::clone(stream.lookup_string(lookup)?);
+            let attr_count = stream.read_u8()?;
+            let attributes = (0..attr_count).map(|_| {
+                let key = Arc::clone(stream.lookup_string(lookup)?);
+                Ok::<_, LoadError>((key, Value::decode(stream, lookup, options)?))
+            }).collect::<Result<HashMap<_, _>, _>>()?;
+            Ok::<_, LoadError>((name, attributes, stream.read_u16::<LittleEndian>()?))
+        };
+        let (name, attributes, child_count) = match read_head() {
+            Ok(head) => head,
+            Err(err) => return (None, Some(err))
+        };
+
+        let mut el = Element { name, attributes, children: Vec::new() };
+        for _ in 0 .. child_count {
+            let (child, err) = Element::decode_partial(stream, lookup, options, keep_partial.saturating_sub(1));
+            if let Some(err) = err {
+                if keep_partial > 0 {
+                    el.children.extend(child);
+                }
+                return (Some(el), Some(err));
+            }
+            el.children.extend(child);
+        }
+        (Some(el), None)
+    }
+
+    /// Skips over the attributes of an element whose name has already been read, returning its child count.
+    pub(crate) fn skip_attributes(stream: &mut dyn io::Read, lookup: &[Arc<str>]) -> Result<u16, LoadError> {
+        let attr_count = stream.read_u8()?;
+        for _ in 0..attr_count {
+            stream.lookup_string(lookup)?;
+            Value::skip(stream)?;
+        }
+
+        Ok(stream.read_u16::<LittleEndian>()?)
+    }
+
+    /// Skips over the rest of an element whose name has already been read, without decoding it.
+    pub(crate) fn skip_body(stream: &mut dyn io::Read, lookup: &[Arc<str>]) -> Result<(), LoadError> {
+        let child_count = Self::skip_attributes(stream, lookup)?;
+        for _ in 0..child_count {
+            stream.lookup_string(lookup)?;
+            Self::skip_body(stream, lookup)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds every string this element would put in the lookup table when encoded,
+    /// in the same order that [`Element::encode`] would add them.
+    pub(crate) fn collect_strings(&self, lookup: &mut IndexSet<Arc<str>>) {
+        if !lookup.contains(&self.name) {
+            lookup.insert(Arc::clone(&self.name));
+        }
+        for (name, value) in self.sorted_attributes() {
+            if !lookup.contains(name) {
+                lookup.insert(Arc::clone(name));
+            }
+            value.collect_string(lookup);
+        }
+        for child in &self.children {
+            child.collect_strings(lookup);
+        }
+    }
+
+    /// Finds NaN and infinite floats in this element and its children,
+    /// either replacing them with `0.0` or failing on the first one.
+    pub(crate) fn check_floats(&mut self, clamp: bool) -> Result<(), StoreError> {
+        for (key, value) in &mut self.attributes {
+            if let Value::Float(float) = value {
+                if float.is_finite() {
+                    continue;
+                }
+                if !clamp {
+                    return Err(StoreError::NonFiniteFloat { element: self.name.to_string(), key: key.to_string() });
+                }
+                *float = 0.0;
+            }
+        }
+        self.children.iter_mut().try_for_each(|child| child.check_floats(clamp))
+    }
+
+    pub(crate) fn encode(self, stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>) -> io::Result<()> {
+        let child_count = self.children.len();
+        self.encode_with_child_count(child_count, stream, lookup)
+    }
+
+    /// Encodes this element and its children, but with a child count that includes children written after it.
+    pub(crate) fn encode_with_child_count(
+        self, child_count: usize, stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>
+    ) -> io::Result<()> {
+        write_lookup_index(stream, lookup, self.name)?;
         let attr_count = u8::try_from(self.attributes.len())
             .map_err(|_| io::Error::other("cannot have more than 255 attributes on an element"))?;
         stream.write_u8(attr_count)?;
 
-        for (name, value) in self.attributes {
-            let name_index = u16::try_from(lookup.insert_full(name).0)
-                .map_err(|_| io::Error::other("cannot store more than 65535 unique strings"))?;
-            stream.write_u16::<LittleEndian>(name_index)?;
+        // Attributes are written in the same order as `sorted_attributes`,
+        // since a HashMap's order would make the output differ between runs
+        for (name, value) in self.attributes.into_iter().sorted_unstable_by(|(a, _), (b, _)| a.cmp(b)) {
+            write_lookup_index(stream, lookup, name)?;
             value.encode(stream, lookup)?;
         }
 
-        let child_count = u16::try_from(self.children.len())
+        let child_count = u16::try_from(child_count)
             .map_err(|_| io::Error::other("element cannot have more than 65535 children"))?;
 
         stream.write_u16::<LittleEndian>(child_count)?;
@@ -173,6 +567,49 @@ impl Element {
 
         Ok(())
     }
+
+    /// Encodes a childless element with one string attribute, without needing to own the string.
+    /// 
+    /// This is how level tilemaps are stored, so they can be written out of a reused buffer.
+    pub(crate) fn encode_text(
+        name: &str, attribute: &str, str: &str, rle: bool,
+        stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>
+    ) -> io::Result<()> {
+        write_lookup_index(stream, lookup, name)?;
+        stream.write_u8(1)?;
+        write_lookup_index(stream, lookup, attribute)?;
+        Value::encode_str(str, rle, stream, lookup)?;
+        stream.write_u16::<LittleEndian>(0)
+    }
+
+    /// Gets the number of bytes this element and its children take up when encoded.
+    /// 
+    /// This doesn't count the string lookup table, or the header and package name that come before the root element,
+    /// so it's a lower bound on the size of a stored map. See [`Value::encoded_size`].
+    #[must_use]
+    pub fn encoded_size(&self) -> usize {
+        // Name index, attribute count, and child count
+        2 + 1 + 2
+            + self.attributes.values().map(|value| 2 + value.encoded_size()).sum::<usize>()
+            + self.children.iter().map(Element::encoded_size).sum::<usize>()
+    }
+
+    /// Calls `f` on this element and every element inside of it, parents before their children.
+    /// 
+    /// `f` is also given the names of the element's ancestors, starting from this one,
+    /// which is useful for saying where something is in error messages.
+    pub fn walk(&self, mut f: impl FnMut(&[&str], &Element)) {
+        fn walk_inner<'el>(el: &'el Element, path: &mut Vec<&'el str>, f: &mut dyn FnMut(&[&str], &Element)) {
+            f(path, el);
+            path.push(&el.name);
+            for child in &el.children {
+                walk_inner(child, path, f);
+            }
+            path.pop();
+        }
+
+        walk_inner(self, &mut Vec::new(), &mut f);
+    }
 }
 
 impl std::fmt::Display for Element {
@@ -180,10 +617,8 @@ impl std::fmt::Display for Element {
         write!(f, "<{}", self.name)?;
 
         let mut inner_text = None;
-        for (name, value) in self.attributes.iter().sorted_unstable_by(
-            |(a, _), (b, _)| a.cmp(b)
-        ) {
-            if name == "innerText" {
+        for (name, value) in self.sorted_attributes() {
+            if &**name == "innerText" {
                 let value = match value {
                     Value::String(value) 
                         | Value::RleString(value) 
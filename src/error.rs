@@ -20,7 +20,11 @@ pub enum LoadError {
     /// An element had a field that was invalid.
     InvalidFieldData(&'static str, String),
     /// An element had an unexpected name for its location.
-    InvalidElementName(String, &'static str)
+    InvalidElementName(String, &'static str),
+    /// There were this many bytes left in the stream after the map ended.
+    /// 
+    /// Only checked for with [`LoadOptions::reject_trailing_data`](crate::LoadOptions::reject_trailing_data).
+    TrailingData(u64)
 }
 
 impl fmt::Display for LoadError {
@@ -41,7 +45,9 @@ impl fmt::Display for LoadError {
             LoadError::InvalidFieldData(name, value) =>
                 write!(f, "element field {name:?} had malformed data: {value}"),
             LoadError::InvalidElementName(value, expected) =>
-                write!(f, "found unexpected element {value:?} when looking for elements of name {expected:?}")
+                write!(f, "found unexpected element {value:?} when looking for elements of name {expected:?}"),
+            LoadError::TrailingData(count) =>
+                write!(f, "found {count} unexpected bytes after the end of the map")
          }
     }
 }
@@ -71,8 +77,130 @@ impl LoadError {
             LoadError::InvalidFieldType(name, v) => LoadError::InvalidFieldType(*name, v.clone()),
             LoadError::InvalidFieldData(name, d) => LoadError::InvalidFieldData(*name, d.clone()),
             LoadError::InvalidElementName(name, expected) => LoadError::InvalidElementName(name.clone(), *expected),
+            LoadError::TrailingData(count) => LoadError::TrailingData(*count),
 
             LoadError::IoError(_) => return None
         } )
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+/// Something that can go wrong when storing a map.
+pub enum StoreError {
+    /// An IO error occurred.
+    IoError(io::Error),
+    /// An attribute held a float that was NaN or infinite, which Celeste can't load.
+    NonFiniteFloat {
+        /// The name of the element the float was in.
+        element: String,
+        /// The name of the attribute the float was in.
+        key: String
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreError::IoError(err) =>
+                write!(f, "io error: {err}"),
+            StoreError::NonFiniteFloat { element, key } =>
+                write!(f, "attribute {key:?} of element {element:?} was not a finite number")
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<io::Error> for StoreError {
+    fn from(value: io::Error) -> Self {
+        StoreError::IoError(value)
+    }
+}
+
+impl From<StoreError> for io::Error {
+    fn from(value: StoreError) -> Self {
+        match value {
+            StoreError::IoError(err) => err,
+            other => io::Error::new(io::ErrorKind::InvalidData, other)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+/// Something that can go wrong when merging levels with [`Map::merge_levels`](crate::Map::merge_levels).
+pub enum MergeError {
+    /// Both indices pointed to the same level.
+    SameLevel,
+    /// A level index was out of bounds.
+    OutOfBounds(usize),
+    /// The levels didn't share an edge.
+    NotAdjacent,
+    /// The levels weren't aligned to the tile grid.
+    Misaligned,
+    /// The merged level would be too large, either for its coordinates or for [`Level::new`](crate::Level::new).
+    TooLarge
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::SameLevel =>
+                write!(f, "cannot merge a level with itself"),
+            MergeError::OutOfBounds(index) =>
+                write!(f, "level index out of bounds: {index}"),
+            MergeError::NotAdjacent =>
+                write!(f, "levels must share an edge to be merged"),
+            MergeError::Misaligned =>
+                write!(f, "levels must be aligned to the tile grid to be merged"),
+            MergeError::TooLarge =>
+                write!(f, "merged level is too large")
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+/// Something that looked wrong in a map, but didn't stop it from loading.
+/// See [`Map::load_with_warnings`](crate::Map::load_with_warnings).
+pub enum LoadWarning {
+    /// An entity in a level's `entities` looks like a trigger, or a trigger in its `triggers` looks like an entity,
+    /// going by [`Entity::looks_like_trigger`](crate::Entity::looks_like_trigger).
+    MisplacedEntity {
+        /// The name of the level.
+        level: String,
+        /// The name of the entity.
+        name: String,
+        /// The ID of the entity.
+        id: i32,
+        /// Whether it was in `triggers`.
+        in_triggers: bool
+    },
+    /// An entity or trigger had a negative width or height.
+    /// 
+    /// These load as they are, but can't be set with [`Entity::set_size`](crate::Entity::set_size).
+    NegativeEntitySize {
+        /// The name of the level.
+        level: String,
+        /// The name of the entity.
+        name: String,
+        /// The ID of the entity.
+        id: i32
+    }
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadWarning::MisplacedEntity { level, name, id, in_triggers: true } =>
+                write!(f, "trigger {name:?} (id {id}) in level {level:?} looks like an entity"),
+            LoadWarning::MisplacedEntity { level, name, id, in_triggers: false } =>
+                write!(f, "entity {name:?} (id {id}) in level {level:?} looks like a trigger"),
+            LoadWarning::NegativeEntitySize { level, name, id } =>
+                write!(f, "entity {name:?} (id {id}) in level {level:?} has a negative size")
+        }
+    }
+}
@@ -1,16 +1,35 @@
-use std::io::{self, Cursor, Seek};
+use std::{io::{self, Cursor, Seek}, sync::Arc};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use itertools::Itertools;
 
 use crate::LoadError;
 
 pub trait ReadExt {
-    fn read_rle_string(&mut self) -> io::Result<String>;
-    fn lookup_string<'arr>(&mut self, arr: &'arr Vec<String>) -> Result<&'arr str, LoadError>;
-    fn read_string(&mut self) -> io::Result<String>;
+    fn read_rle_string(&mut self, lossy: bool) -> io::Result<String>;
+    fn lookup_string<'arr>(&mut self, arr: &'arr [Arc<str>]) -> Result<&'arr Arc<str>, LoadError>;
+    fn read_string(&mut self, lossy: bool) -> io::Result<String>;
     fn read_variable_length_int(&mut self) -> io::Result<usize>;
 }
 
+/// Gets the number of bytes a variable-length integer takes up when written.
+pub fn variable_length_int_size(mut int: usize) -> usize {
+    let mut size = 1;
+    while int > 0b0111_1111 {
+        int >>= 7;
+        size += 1;
+    }
+    size
+}
+
+/// Gets the number of bytes a string takes up when written with run-length encoding, not including its length prefix.
+pub fn rle_string_size(str: &str) -> usize {
+    str.bytes()
+        .chunk_by(|byte| *byte)
+        .into_iter()
+        .map(|(_, run)| run.count().div_ceil(u8::MAX as usize) * 2)
+        .sum()
+}
+
 pub trait WriteExt {
     fn write_rle_string(&mut self, str: &str) -> io::Result<()>;
     fn write_string(&mut self, str: &str) -> io::Result<()>;
@@ -36,37 +55,56 @@ impl<T: io::Read + ?Sized> ReadExt for T {
     }
 
     /// Reads a variable-length string from the file.
-    fn read_string(&mut self) -> io::Result<String> {
+    /// 
+    /// If `lossy` is set, invalid UTF-8 is replaced with U+FFFD instead of being an error.
+    fn read_string(&mut self, lossy: bool) -> io::Result<String> {
         let length = self.read_variable_length_int()?;
         let mut buf = vec![0u8; length];
         self.read_exact(&mut buf)?;
         String::from_utf8(buf)
-            .map_err(|err| io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("string is not valid utf-8: {}", String::from_utf8_lossy(err.as_bytes()))
-            ))
+            .or_else(|err| if lossy {
+                Ok(String::from_utf8_lossy(err.as_bytes()).into_owned())
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("string is not valid utf-8: {}", String::from_utf8_lossy(err.as_bytes()))
+                ))
+            })
     }
 
     /// Grabs a string from the given array from an index in the file.
-    fn lookup_string<'arr>(&mut self, arr: &'arr Vec<String>) -> Result<&'arr str, LoadError> {
+    fn lookup_string<'arr>(&mut self, arr: &'arr [Arc<str>]) -> Result<&'arr Arc<str>, LoadError> {
         let index = self.read_u16::<LittleEndian>()? as usize;
         arr.get(index)
-            .map(String::as_str)
             .ok_or(LoadError::InvalidString(index))
     }
 
     /// Reads a run-length encoded string.
-    fn read_rle_string(&mut self) -> io::Result<String> {
+    /// 
+    /// Runs with a count of zero are skipped. The size has to be a whole number of (count, byte) pairs,
+    /// since reading half of a pair would leave the rest of the stream misaligned.
+    /// `lossy` works the same as in [`ReadExt::read_string`].
+    fn read_rle_string(&mut self, lossy: bool) -> io::Result<String> {
         let size = self.read_u16::<LittleEndian>()? as usize;
-        (0..(size / 2))
-            .map(|_| {
-                let times = self.read_u8()?;
-                let chr = self.read_u8()?;
-                Ok::<_, io::Error>(std::iter::repeat(chr).take(times as usize))
-            }).process_results(|iter| {
-                String::from_utf8(iter.flatten().collect::<Vec<u8>>())
-                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.utf8_error()))
-            })?
+        if !size.is_multiple_of(2) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("run-length encoded string has an odd size of {size} bytes")
+            ));
+        }
+        let mut runs = vec![0; size];
+        self.read_exact(&mut runs)?;
+
+        let mut bytes = Vec::with_capacity(runs.chunks_exact(2).map(|run| run[0] as usize).sum());
+        for run in runs.chunks_exact(2) {
+            bytes.extend(std::iter::repeat_n(run[1], run[0] as usize));
+        }
+        String::from_utf8(bytes)
+            .or_else(|err| if lossy {
+                Ok(String::from_utf8_lossy(err.as_bytes()).into_owned())
+            } else {
+                Err(io::Error::new(io::ErrorKind::InvalidData, err.utf8_error()))
+            })
     }
 }
 
@@ -119,4 +157,57 @@ impl<T: io::Write + ?Sized> WriteExt for T {
             if !cont { break Ok(()); }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{ReadExt, WriteExt};
+
+    fn round_trip(int: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.write_variable_length_int(int).unwrap();
+        let mut slice = buf.as_slice();
+        assert_eq!(slice.read_variable_length_int().unwrap(), int);
+        assert!(slice.is_empty(), "{int} left bytes unread");
+        buf
+    }
+
+    #[test]
+    fn variable_length_int_boundaries() {
+        assert_eq!(round_trip(0), [0x00]);
+        assert_eq!(round_trip(127), [0x7F]);
+        assert_eq!(round_trip(128), [0x80, 0x01]);
+        assert_eq!(round_trip(300), [0xAC, 0x02]);
+        assert_eq!(round_trip(16383), [0xFF, 0x7F]);
+        assert_eq!(round_trip(16384), [0x80, 0x80, 0x01]);
+
+        let max = round_trip(usize::MAX);
+        assert_eq!(max.len(), usize::BITS.div_ceil(7) as usize);
+        assert!(max[.. max.len() - 1].iter().all(|&byte| byte == 0xFF));
+        assert_eq!(round_trip(usize::MAX - 1)[0], 0xFE);
+    }
+
+    #[test]
+    fn rle_string_sizes() {
+        let mut buf = Vec::new();
+        buf.write_rle_string("aaab").unwrap();
+        assert_eq!(buf, [4, 0, 3, b'a', 1, b'b']);
+        assert_eq!(buf.as_slice().read_rle_string(false).unwrap(), "aaab");
+
+        // Zero-length runs don't add anything
+        assert_eq!([4, 0, 0, b'x', 2, b'y'].as_slice().read_rle_string(false).unwrap(), "yy");
+        // An odd size would split a pair in half
+        let odd = [3, 0, 3, b'a', 1];
+        assert_eq!(odd.as_slice().read_rle_string(false).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        // And a size past the end of the stream can't be read at all
+        let truncated = [6, 0, 3, b'a'];
+        assert_eq!(truncated.as_slice().read_rle_string(false).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn variable_length_int_too_long() {
+        let too_long = vec![0xFF; usize::BITS.div_ceil(7) as usize + 1];
+        assert!(too_long.as_slice().read_variable_length_int().is_err());
+    }
+}
@@ -39,61 +39,261 @@ map.store(&mut f, true).unwrap();
 */
 
 
-use std::io::{self, Cursor};
+use std::{fs::File, io::{self, BufReader, Cursor}, path::PathBuf, sync::Arc};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt as _};
 
 mod ext;
 use ext::{ReadExt, WriteExt};
 
 mod error;
-pub use error::LoadError;
+pub use error::{LoadError, LoadWarning, MergeError, StoreError};
 
 mod element;
-pub use element::{Element, Value}; 
+pub use element::{Element, Value, ValueKind}; 
 
 mod map_data;
 use indexmap::IndexSet;
-pub use map_data::{Map, Level, Filler, Entity, Decal, LevelData};
+pub use map_data::{Map, Level, Filler, Entity, EntityKind, Decal, LevelData, LevelStats, WindPattern, Direction, ElementPath, TILE_SIZE};
 
 mod map_serde;
 pub use map_serde::MapElement;
+use map_serde::LevelTilemaps;
 
 mod tilemap;
-pub use tilemap::Tilemap; 
+pub use tilemap::{Tilemap, TilemapPatch};
+
+mod patch;
+pub use patch::{MapPatch, LevelListPatch, LevelPatch, EntityListPatch};
+
+mod options;
+pub use options::{LoadOptions, StoreOptions};
+
+/// A step of loading a map, reported by [`Map::load_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LoadPhase {
+    /// The header and package name were read.
+    HeaderRead,
+    /// The string lookup table was read, holding this many strings.
+    LookupTableRead(usize),
+    /// This many elements have been decoded so far.
+    /// 
+    /// This is reported every [`LoadPhase::ELEMENT_INTERVAL`] elements, and once more with the total when decoding finishes.
+    ElementsDecoded(usize)
+}
+
+impl LoadPhase {
+    /// How many elements are decoded between each [`LoadPhase::ElementsDecoded`].
+    pub const ELEMENT_INTERVAL: usize = 1000;
+}
 
 impl Map {
+    /// The header at the start of a map, unless it was stored without one or with [`Map::header`] set.
+    pub const HEADER: &'static str = "CELESTE MAP";
+
     /// Loads a [`Map`] from a readable stream, with Celeste's map format.
     /// 
     /// # Errors
     /// Errors if the map fails to load. See [`LoadError`] for more information.
     pub fn load(stream: &mut dyn io::Read, check_header: bool) -> Result<Map, LoadError> {
-        if check_header {
-            let header = stream.read_string()?;
-            if header != "CELESTE MAP" {
-                return Err(LoadError::InvalidHeader(header));
+        Map::load_with_options(stream, LoadOptions { check_header, ..Default::default() })
+    }
+
+    /// Loads a [`Map`] from a readable stream, with Celeste's map format, using the given [`LoadOptions`].
+    /// 
+    /// # Errors
+    /// Errors if the map fails to load. See [`LoadError`] for more information.
+    pub fn load_with_options(stream: &mut dyn io::Read, options: LoadOptions) -> Result<Map, LoadError> {
+        Map::load_with_warnings(stream, options).map(|(map, _)| map)
+    }
+
+    /// Loads a [`Map`] from a readable stream like [`Map::load_with_options`],
+    /// also returning everything that looked wrong but didn't stop it from loading.
+    /// 
+    /// # Errors
+    /// Errors if the map fails to load. See [`LoadError`] for more information.
+    pub fn load_with_warnings(stream: &mut dyn io::Read, options: LoadOptions) -> Result<(Map, Vec<LoadWarning>), LoadError> {
+        let map = Map::from_element(load_element(stream, options, &mut |_| ())?, options)?;
+        let warnings = map_serde::load_warnings(&map);
+        if options.strict_entity_containers {
+            map_serde::check_entity_containers(&warnings)?;
+        }
+        Ok((map, warnings))
+    }
+
+    /// Loads a [`Map`] from a readable stream, with Celeste's map format, reporting progress along the way.
+    /// 
+    /// `progress` is called as each [`LoadPhase`] finishes, which is useful for showing progress bars for large maps.
+    /// 
+    /// # Errors
+    /// Errors if the map fails to load. See [`LoadError`] for more information.
+    pub fn load_with_progress(
+        stream: &mut dyn io::Read, check_header: bool, mut progress: impl FnMut(LoadPhase)
+    ) -> Result<Map, LoadError> {
+        load_element(stream, LoadOptions { check_header, ..Default::default() }, &mut progress)?.try_into()
+    }
+
+    /// Loads a [`Map`] from a readable stream, with Celeste's map format, expecting it to start with a custom header
+    /// instead of `CELESTE MAP`.
+    /// 
+    /// The header is kept in [`Map::header`], so storing the map with a header writes the same one back.
+    /// 
+    /// # Errors
+    /// Errors with [`LoadError::InvalidHeader`] if the map doesn't start with `expected`,
+    /// or if the rest of the map fails to load. See [`LoadError`] for more information.
+    pub fn load_with_header(stream: &mut dyn io::Read, expected: &str) -> Result<Map, LoadError> {
+        let header = Map::read_header(stream)?;
+        if header != expected {
+            return Err(LoadError::InvalidHeader(header));
+        }
+        let mut map = Map::load(stream, false)?;
+        map.header = (header != Map::HEADER).then_some(header);
+        Ok(map)
+    }
+
+    /// Reads the header at the start of a map, whatever it is.
+    /// 
+    /// The rest of the map can be loaded afterwards by passing `false` for `check_header`, like with [`Map::load`].
+    /// This is how to find out what header a map has, since loading without checking the header doesn't read one at all.
+    /// 
+    /// # Errors
+    /// Errors if the header can't be read.
+    pub fn read_header(stream: &mut dyn io::Read) -> Result<String, LoadError> {
+        Ok(stream.read_string(false)?)
+    }
+
+    /// Loads as much of a [`Map`] as possible from a readable stream, with Celeste's map format,
+    /// for recovering maps from files that were cut off or corrupted partway through.
+    /// 
+    /// Loading stops at the first error, and the map is made from everything before it.
+    /// Levels (and anything else inside the map's top-level elements) are only kept if they were read in full,
+    /// so the map has every complete level before the error, but nothing from the one it happened in.
+    /// 
+    /// The map is [`None`] if not even the start of the map could be read.
+    /// Every error that happened is returned with it, so an empty list means the whole map loaded normally.
+    pub fn load_partial(stream: &mut dyn io::Read, check_header: bool) -> (Option<Map>, Vec<LoadError>) {
+        let (package, lookup) = match load_preamble(stream, LoadOptions { check_header, ..Default::default() }, &mut |_| ()) {
+            Ok(preamble) => preamble,
+            Err(err) => return (None, vec![err])
+        };
+        // Keeping partial elements one level down keeps `levels`, but not the level that was cut off
+        let (el, err) = Element::decode_partial(stream, &lookup, LoadOptions::default(), 1);
+        let mut errors = Vec::from_iter(err);
+        let Some(mut el) = el else { return (None, errors) };
+        el.attributes.insert("_package".into(), package.into());
+
+        match Map::try_from(el) {
+            Ok(map) => (Some(map), errors),
+            Err(err) => {
+                errors.push(err);
+                (None, errors)
+            }
+        }
+    }
+
+    /// Loads only the levels of a map from a readable stream, with Celeste's map format.
+    /// 
+    /// Everything other than the `levels` element (stylegrounds, filler, etc.) is skipped over without being decoded,
+    /// which is faster for tools that only care about level geometry.
+    /// 
+    /// # Errors
+    /// Errors if the map fails to load. See [`LoadError`] for more information.
+    pub fn load_levels_only(stream: &mut dyn io::Read, check_header: bool) -> Result<Vec<Level>, LoadError> {
+        let (_, lookup) = load_preamble(stream, LoadOptions { check_header, ..Default::default() }, &mut |_| ())?;
+
+        let name = stream.lookup_string(&lookup)?;
+        if &**name != "Map" {
+            return Err(LoadError::InvalidElementName(name.to_string(), "Map"));
+        }
+        let child_count = Element::skip_attributes(stream, &lookup)?;
+
+        let mut levels = Vec::new();
+        for _ in 0 .. child_count {
+            let name = stream.lookup_string(&lookup)?;
+            if &**name == "levels" {
+                levels = Element::decode_body(stream, &lookup, Arc::clone(name), LoadOptions::default(), &mut || ())?
+                    .children
+                    .into_iter()
+                    .map(Level::try_from)
+                    .collect::<Result<_, _>>()?;
+            } else {
+                Element::skip_body(stream, &lookup)?;
             }
         }
-        
-        let package = stream.read_string()?;
-        let lookup_length = stream.read_u16::<LittleEndian>()?;
-        let lookup = (0 .. lookup_length)
-            .map(|_| stream.read_string())
-            .collect::<Result<Vec<_>, _>>()?;
-        
-        let mut map = Element::decode(stream, &lookup)?;
-        map.attributes.insert("_package".to_string(), package.into());
 
-        map.try_into()
+        Ok(levels)
     }
 
     /// Stores this [`Map`] into a writable stream, with Celeste's map format.
     /// 
     /// # Errors
-    /// Errors if an IO error occurs during writing.
+    /// Errors if an IO error occurs during writing,
+    /// or with [`io::ErrorKind::InvalidData`] if the map holds a NaN or infinite float (see [`StoreError::NonFiniteFloat`]).
     pub fn store(self, stream: &mut dyn io::Write, write_header: bool) -> io::Result<()> {
-        let mut buf = Cursor::new(Vec::new());
+        Ok(self.store_with_options(stream, StoreOptions { write_header, ..Default::default() })?)
+    }
+
+    /// Stores this [`Map`] into a writable stream, with Celeste's map format, using the given [`StoreOptions`].
+    /// 
+    /// Nothing is written if the map can't be stored because of its contents.
+    /// 
+    /// # Errors
+    /// Errors if an IO error occurs during writing, or if the map holds something Celeste can't load.
+    /// See [`StoreError`] for more information.
+    pub fn store_with_options(mut self, stream: &mut dyn io::Write, options: StoreOptions) -> Result<(), StoreError> {
+        if options.sort_extra_children {
+            self.sort_extra_children();
+        }
+        let header = self.header.take();
+        let (package, mut el) = self.into_root();
+        el.check_floats(options.clamp_non_finite_floats)?;
+        if options.omit_defaults {
+            map_serde::omit_level_defaults(&mut el);
+        }
+        let header = options.write_header.then(|| header.as_deref().unwrap_or(Map::HEADER));
+        Ok(store_element(el, &package, stream, header)?)
+    }
+
+    /// Stores this [`Map`] into a writable stream, with Celeste's map format,
+    /// without buffering the encoded map in memory first.
+    /// 
+    /// The string table has to come before everything else in the format,
+    /// so this walks the map twice: once to build the table, and once to write the elements straight to the stream.
+    /// Level tilemaps are stored into one reused buffer as they're written, instead of all being stored up front.
+    /// The output is the same as [`Map::store`].
+    /// 
+    /// # Errors
+    /// Errors the same way as [`Map::store`].
+    pub fn store_streaming(mut self, stream: &mut dyn io::Write, write_header: bool) -> io::Result<()> {
+        let header = self.header.take();
+        let levels = std::mem::take(&mut self.levels);
+        let (package, mut el) = self.into_root();
+        el.check_floats(false)?;
+        let levels = levels.into_iter()
+            .map(|level| {
+                let (mut el, tilemaps) = level.into_element_without_tilemaps();
+                el.check_floats(false)?;
+                Ok((el, tilemaps))
+            })
+            .collect::<Result<Vec<_>, StoreError>>()?;
+        let header = write_header.then(|| header.as_deref().unwrap_or(Map::HEADER));
+        store_element_streaming(el, levels, &package, stream, header)
+    }
+
+    /// Computes the string lookup table that [`Map::store`] would write for this map, without storing it.
+    /// 
+    /// The strings are in the same order they'd be written in.
+    /// Strings too long to be looked up (like most tilemaps) are stored inline, so they aren't in the table.
+    #[must_use]
+    pub fn compute_string_table(&self) -> IndexSet<String> {
+        let (_, el) = self.clone().into_root();
         let mut strings = IndexSet::new();
+        el.collect_strings(&mut strings);
+        strings.into_iter().map(|string| string.to_string()).collect()
+    }
 
+    /// Converts this map into its root element, splitting out the package name.
+    fn into_root(self) -> (String, Element) {
         let mut el = Element::from(self);
         let package = el.attributes.remove("_package")
             .map_or(String::new(), |val| match val {
@@ -101,26 +301,283 @@ impl Map {
                 Value::Float(float) => float.to_string(),
                 Value::Integer(int) => int.to_string(),
                 Value::String(str) | Value::RleString(str)
-                    => str
+                    => str,
+                other @ Value::Unknown(..) => other.to_string()
             });
-        el.encode(&mut buf, &mut strings)?;
-        
-        let Ok(lookup_length) = u16::try_from(strings.len())
-        else {
-            return Err(io::Error::other("cannot store more than 65535 unique strings in a map"))
-        };
+        (package, el)
+    }
 
-        if write_header {
-            stream.write_string("CELESTE MAP")?;
+    /// Loads a [`Map`] from a file by memory-mapping it, with Celeste's map format.
+    /// 
+    /// This avoids buffered reads when scanning many maps at once.
+    /// 
+    /// # Errors
+    /// Errors if the file can't be opened or mapped, or if the map fails to load.
+    /// See [`LoadError`] for more information.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated, by this process or any other, until this returns.
+    /// Reading a mapped page after the file shrinks raises `SIGBUS`,
+    /// and changing the contents while they're being read is undefined behavior.
+    #[cfg(feature = "memmap")]
+    pub unsafe fn load_mmap(path: impl AsRef<std::path::Path>) -> Result<Map, LoadError> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: The caller guarantees the file stays as it is until we return,
+        // and the mapping is dropped before then.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Map::load(&mut &mmap[..], true)
+    }
+
+    /// Loads a [`Map`] from an asynchronous reader, with Celeste's map format.
+    /// 
+    /// The whole stream is read into memory first, and then decoded the same way as [`Map::load`].
+    /// 
+    /// # Errors
+    /// Errors if the map fails to load. See [`LoadError`] for more information.
+    #[cfg(feature = "async")]
+    pub async fn load_async(reader: &mut (impl tokio::io::AsyncRead + Unpin), check_header: bool) -> Result<Map, LoadError> {
+        use tokio::io::AsyncReadExt as _;
+
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+        Map::load(&mut &buf[..], check_header)
+    }
+
+    /// Stores this [`Map`] into an asynchronous writer, with Celeste's map format.
+    /// 
+    /// The map is encoded into memory first the same way as [`Map::store`], and then written all at once.
+    /// 
+    /// # Errors
+    /// Errors if an IO error occurs during writing.
+    #[cfg(feature = "async")]
+    pub async fn store_async(self, writer: &mut (impl tokio::io::AsyncWrite + Unpin), write_header: bool) -> io::Result<()> {
+        use tokio::io::AsyncWriteExt as _;
+
+        let mut buf = Vec::new();
+        self.store(&mut buf, write_header)?;
+        writer.write_all(&buf).await?;
+        writer.flush().await
+    }
+}
+
+impl Element {
+    /// Decodes a map into its raw root element, with Celeste's map format.
+    /// 
+    /// This is the low-level counterpart to [`Map::load`], for maps that need to be inspected
+    /// or fixed up before (or instead of) being converted into a [`Map`].
+    /// Like [`Map::load`], the package name is put in the root element's `_package` attribute.
+    /// 
+    /// # Errors
+    /// Errors if the map fails to decode. See [`LoadError`] for more information.
+    pub fn from_map_bytes(mut bytes: &[u8], check_header: bool) -> Result<Element, LoadError> {
+        load_element(&mut bytes, LoadOptions { check_header, ..Default::default() }, &mut |_| ())
+    }
+
+    /// Serializes this element as the root of a map, with Celeste's map format.
+    /// 
+    /// This is the low-level counterpart to [`Map::store`], for element trees built by hand.
+    /// Any `_package` attribute is dropped, since the package is written separately.
+    /// 
+    /// # Errors
+    /// Errors if the element tree can't be stored, e.g. if it has too many unique strings or a NaN float.
+    pub fn to_map_bytes(mut self, package: &str, write_header: bool) -> Result<Vec<u8>, StoreError> {
+        self.attributes.remove("_package");
+        self.check_floats(false)?;
+        let mut buf = Vec::new();
+        store_element(self, package, &mut buf, write_header.then_some(Map::HEADER))?;
+        Ok(buf)
+    }
+}
+
+/// Loads every map in a list of files, without stopping at the first failure.
+/// 
+/// Returns each path alongside the result of loading it, in the same order they were given.
+/// This is useful for batch tools that should carry on past corrupt files.
+#[must_use]
+pub fn load_many(paths: &[PathBuf], check_header: bool) -> Vec<(PathBuf, Result<Map, LoadError>)> {
+    paths.iter()
+        .map(|path| {
+            let result = File::open(path)
+                .map_err(LoadError::from)
+                .and_then(|file| Map::load(&mut BufReader::new(file), check_header));
+            (path.clone(), result)
+        })
+        .collect()
+}
+
+/// Reads everything before the root element, returning the package name and the string lookup table.
+fn load_preamble(
+    stream: &mut dyn io::Read, options: LoadOptions, progress: &mut dyn FnMut(LoadPhase)
+) -> Result<(String, Vec<Arc<str>>), LoadError> {
+    if options.check_header {
+        let header = stream.read_string(options.lossy_utf8)?;
+        if header != Map::HEADER {
+            return Err(LoadError::InvalidHeader(header));
         }
+    }
+    
+    let package = stream.read_string(options.lossy_utf8)?;
+    progress(LoadPhase::HeaderRead);
+    let lookup_length = stream.read_u16::<LittleEndian>()?;
+    let lookup = (0 .. lookup_length)
+        .map(|_| stream.read_string(options.lossy_utf8).map(Arc::from))
+        .collect::<Result<Vec<_>, _>>()?;
+    progress(LoadPhase::LookupTableRead(lookup.len()));
 
-        stream.write_string(&package)?;
-        stream.write_u16::<LittleEndian>(lookup_length)?;
+    Ok((package, lookup))
+}
 
-        for string in strings.iter() {
-            stream.write_string(string)?;
+/// Decodes the root element of a map, putting the package name in its `_package` attribute.
+fn load_element(stream: &mut dyn io::Read, options: LoadOptions, progress: &mut dyn FnMut(LoadPhase)) -> Result<Element, LoadError> {
+    let (package, lookup) = load_preamble(stream, options, progress)?;
+    
+    let mut decoded = 0;
+    let mut el = Element::decode(stream, &lookup, options, &mut || {
+        decoded += 1;
+        if decoded % LoadPhase::ELEMENT_INTERVAL == 0 {
+            progress(LoadPhase::ElementsDecoded(decoded));
         }
+    })?;
+    if decoded % LoadPhase::ELEMENT_INTERVAL != 0 {
+        progress(LoadPhase::ElementsDecoded(decoded));
+    }
+    if options.reject_trailing_data {
+        let trailing = io::copy(stream, &mut io::sink())?;
+        if trailing > 0 {
+            return Err(LoadError::TrailingData(trailing));
+        }
+    }
+    if options.expand_tilemaps {
+        map_serde::expand_level_sizes(&mut el);
+    }
+    el.attributes.insert("_package".into(), package.into());
+
+    Ok(el)
+}
+
+/// Writes everything that comes before the root element.
+fn store_preamble(stream: &mut dyn io::Write, package: &str, strings: &IndexSet<Arc<str>>, header: Option<&str>) -> io::Result<()> {
+    let Ok(lookup_length) = u16::try_from(strings.len())
+    else {
+        return Err(io::Error::other("cannot store more than 65535 unique strings in a map"))
+    };
+
+    if let Some(header) = header {
+        stream.write_string(header)?;
+    }
+
+    stream.write_string(package)?;
+    stream.write_u16::<LittleEndian>(lookup_length)?;
+
+    for string in strings {
+        stream.write_string(string)?;
+    }
+
+    Ok(())
+}
+
+/// Stores a root element by encoding it into a buffer, then writing the string table and buffer.
+fn store_element(el: Element, package: &str, stream: &mut dyn io::Write, header: Option<&str>) -> io::Result<()> {
+    // Sizing the buffer up front saves a lot of reallocating for large maps
+    let mut buf = Cursor::new(Vec::with_capacity(el.encoded_size()));
+    let mut strings = IndexSet::new();
+
+    el.encode(&mut buf, &mut strings)?;
+    store_preamble(stream, package, &strings, header)?;
+
+    stream.write_all(buf.get_ref())
+}
+
+/// Stores a root element by collecting the string table up front, then encoding straight into the stream.
+/// 
+/// The given levels are written after the children of the root's last child, which holds the levels.
+fn store_element_streaming(
+    mut el: Element, levels: Vec<(Element, LevelTilemaps)>,
+    package: &str, stream: &mut dyn io::Write, header: Option<&str>
+) -> io::Result<()> {
+    let mut strings = IndexSet::new();
+    let mut tiles = String::new();
 
-        stream.write_all(buf.get_ref())
+    el.collect_strings(&mut strings);
+    for (level, tilemaps) in &levels {
+        level.collect_strings(&mut strings);
+        tilemaps.collect_strings(&mut tiles, &mut strings);
     }
-}
\ No newline at end of file
+    store_preamble(stream, package, &strings, header)?;
+
+    let Some(levels_el) = el.children.pop() else {
+        return el.encode(stream, &mut strings);
+    };
+    let child_count = el.children.len() + 1;
+    el.encode_with_child_count(child_count, stream, &mut strings)?;
+    let child_count = levels_el.children.len() + levels.len();
+    levels_el.encode_with_child_count(child_count, stream, &mut strings)?;
+    for (level, tilemaps) in levels {
+        let child_count = level.children.len() + LevelTilemaps::COUNT;
+        level.encode_with_child_count(child_count, stream, &mut strings)?;
+        tilemaps.encode(&mut tiles, stream, &mut strings)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use crate::{Decal, Element, Entity, Level, Map, Value};
+
+    #[test]
+    fn streaming_store_matches_buffered() {
+        let leaf = |name: &str, attributes: HashMap<Arc<str>, Value>| Element {
+            name: name.into(),
+            attributes,
+            children: vec![]
+        };
+        let root = Element {
+            name: "Map".into(),
+            attributes: HashMap::new(),
+            children: vec![
+                leaf("Filler", HashMap::new()),
+                Element {
+                    name: "levels".into(),
+                    attributes: HashMap::new(),
+                    children: vec![leaf("level", HashMap::from([
+                        ("name".into(), Value::String("a-00".into())),
+                        ("width".into(), Value::Integer(320)),
+                        ("dark".into(), Value::Boolean(true)),
+                        ("music".into(), Value::String("event:/music/lvl1/main".into())),
+                        ("innerText".into(), Value::String("0".repeat(100))),
+                        ("solids".into(), Value::RleString("1111000".into())),
+                        ("x".into(), Value::Float(1.5)),
+                    ]))]
+                }
+            ]
+        };
+
+        let mut buffered = Vec::new();
+        super::store_element(root.clone(), "pkg", &mut buffered, Some(Map::HEADER)).unwrap();
+        let mut streamed = Vec::new();
+        super::store_element_streaming(root, vec![], "pkg", &mut streamed, Some(Map::HEADER)).unwrap();
+
+        assert_eq!(buffered, streamed);
+    }
+
+    #[test]
+    fn computed_string_table_matches_stored() {
+        let mut level = Level::new("a-00", 320, 184).unwrap();
+        level.solids.map_cells(|_| '1');
+        level.entities.push(Entity { name: "player".into(), id: 1, ..Default::default() });
+        level.fg_decals.push(Decal { texture: "decals/1-forsakencity/flag.png".into(), ..Default::default() });
+        let map = Map { package: "pkg".into(), levels: vec![level], bg_color: Some([1, 2, 3, 4]), ..Default::default() };
+
+        let table = map.compute_string_table();
+        let mut bytes = Vec::new();
+        map.store(&mut bytes, true).unwrap();
+        let (_, lookup) = super::load_preamble(&mut &bytes[..], super::LoadOptions::default(), &mut |_| ()).unwrap();
+
+        assert!(table.iter().map(String::as_str).eq(lookup.iter().map(|string| &**string)));
+        assert!(lookup.iter().any(|string| &**string == "player"));
+        assert!(!lookup.iter().any(|string| string.starts_with("1111")));
+    }
+}
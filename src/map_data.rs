@@ -1,7 +1,9 @@
 
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::{BTreeSet, HashMap}, fmt::{self, Write as _}, hash::Hash, iter, str::FromStr};
 
-use crate::{Element, Tilemap, Value};
+use itertools::Itertools as _;
+
+use crate::{element::{attributes_semantic_eq, children_semantic_eq, color_to_hex, string_payload_size}, map_serde::parse_color, tilemap::TilemapCell, Element, LoadError, MergeError, Tilemap, Value, ValueKind};
 
 #[derive(Debug, Clone, PartialEq, Default)]
 /// A Celeste custom map.
@@ -14,9 +16,736 @@ pub struct Map {
     pub backgrounds: Vec<Element>, // Style::Backgrounds
     pub bg_color: Option<[u8; 4]>, // Style.color
     pub extra_data: HashMap<String, Value>,
-    pub extra_children: Vec<Element>
+    pub extra_children: Vec<Element>,
+    pub header: Option<String> // the header, if it isn't "CELESTE MAP" (not part of the element tree)
+}
+
+
+impl Map {
+    /// Compares two maps by content, rather than exactly.
+    /// 
+    /// Typed fields are compared as usual, but `extra_data` and entity values are compared
+    /// with [`Value::semantic_eq`], and `extra_children` are compared regardless of their order.
+    /// This is useful for checking that a tool's output matches a reference map.
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Map) -> bool {
+        self.package == other.package
+            && self.filler == other.filler
+            && self.bg_color == other.bg_color
+            && self.levels.len() == other.levels.len()
+            && self.levels.iter().zip(&other.levels).all(|(a, b)| a.semantic_eq(b))
+            && self.foregrounds.len() == other.foregrounds.len()
+            && self.foregrounds.iter().zip(&other.foregrounds).all(|(a, b)| a.semantic_eq(b))
+            && self.backgrounds.len() == other.backgrounds.len()
+            && self.backgrounds.iter().zip(&other.backgrounds).all(|(a, b)| a.semantic_eq(b))
+            && attributes_semantic_eq(&self.extra_data, &other.extra_data)
+            && children_semantic_eq(&self.extra_children, &other.extra_children)
+    }
+
+    /// Removes every entity with the given name from every level, returning how many were removed.
+    /// 
+    /// This doesn't touch triggers.
+    pub fn remove_entities_named(&mut self, name: &str) -> usize {
+        self.levels.iter_mut()
+            .map(|level| {
+                let before = level.entities.len();
+                level.entities.retain(|entity| entity.name != name);
+                before - level.entities.len()
+            })
+            .sum()
+    }
+
+    /// Removes every decal with the given texture from every level, returning how many were removed.
+    /// 
+    /// This removes both background and foreground decals.
+    pub fn remove_decals_with_texture(&mut self, texture: &str) -> usize {
+        self.levels.iter_mut()
+            .map(|level| {
+                let before = level.bg_decals.len() + level.fg_decals.len();
+                level.bg_decals.retain(|decal| decal.texture != texture);
+                level.fg_decals.retain(|decal| decal.texture != texture);
+                before - level.bg_decals.len() - level.fg_decals.len()
+            })
+            .sum()
+    }
 }
 
+impl Map {
+    /// Merges two spatially adjacent levels into one, replacing the first with the merged level
+    /// and removing the second.
+    /// 
+    /// The merged level covers the union of both levels' rectangles,
+    /// with both sets of tilemaps copied into place and all entities, triggers, and decals
+    /// moved to stay where they were in the world. Metadata and the name are kept from the first level.
+    /// 
+    /// # Errors
+    /// Errors if either index is out of bounds, the indices are equal,
+    /// the levels don't share an edge, the levels aren't aligned to the tile grid,
+    /// or the merged level would be too large. The map is left unchanged if this errors.
+    pub fn merge_levels(&mut self, a: usize, b: usize) -> Result<(), MergeError> {
+        if a == b {
+            return Err(MergeError::SameLevel);
+        }
+        if a >= self.levels.len() || b >= self.levels.len() {
+            return Err(MergeError::OutOfBounds(a.max(b)));
+        }
+        let (first, second) = (&self.levels[a], &self.levels[b]);
+
+        let rect = |level: &Level| Some((
+            level.data.position.0, level.data.position.1,
+            level.data.position.0.checked_add(level.data.size.0)?, level.data.position.1.checked_add(level.data.size.1)?
+        ));
+        let (a_left, a_top, a_right, a_bottom) = rect(first).ok_or(MergeError::TooLarge)?;
+        let (b_left, b_top, b_right, b_bottom) = rect(second).ok_or(MergeError::TooLarge)?;
+
+        let horizontal = (a_right == b_left || b_right == a_left) && a_top < b_bottom && b_top < a_bottom;
+        let vertical = (a_bottom == b_top || b_bottom == a_top) && a_left < b_right && b_left < a_right;
+        if !(horizontal || vertical) {
+            return Err(MergeError::NotAdjacent);
+        }
+
+        let (left, top) = (a_left.min(b_left), a_top.min(b_top));
+        let (right, bottom) = (a_right.max(b_right), a_bottom.max(b_bottom));
+        let width = right.checked_sub(left).ok_or(MergeError::TooLarge)?;
+        let height = bottom.checked_sub(top).ok_or(MergeError::TooLarge)?;
+        if [a_left, a_top, b_left, b_top, width, height].iter().any(|v| v % TILE_SIZE != 0) {
+            return Err(MergeError::Misaligned);
+        }
+
+        let mut merged = Level::new(first.name.clone(), width, height).ok_or(MergeError::TooLarge)?;
+        merged.data = LevelData {
+            position: (left, top),
+            size: (width, height),
+            ..first.data.clone()
+        };
+        merged.extra_data.clone_from(&first.extra_data);
+
+        for level in [first, second] {
+            // Both levels are inside the union, so these can't overflow or be negative
+            let offset = (level.data.position.0 - left, level.data.position.1 - top);
+            let (tile_x, tile_y) = ((offset.0 / TILE_SIZE).unsigned_abs() as usize, (offset.1 / TILE_SIZE).unsigned_abs() as usize);
+            merged.bg.blit(&level.bg, tile_x, tile_y);
+            merged.solids.blit(&level.solids, tile_x, tile_y);
+            merged.bg_tiles.blit(&level.bg_tiles, tile_x, tile_y);
+            merged.fg_tiles.blit(&level.fg_tiles, tile_x, tile_y);
+            merged.obj_tiles.blit(&level.obj_tiles, tile_x, tile_y);
+
+            #[allow(clippy::cast_precision_loss)]
+            let (dx, dy) = (offset.0 as f32, offset.1 as f32);
+            let shift = |(x, y): (f32, f32)| (x + dx, y + dy);
+            let shift_entity = |entity: &Entity| {
+                let mut entity = entity.clone();
+                entity.position = shift(entity.position);
+                for node in &mut entity.nodes {
+                    *node = shift(*node);
+                }
+                entity
+            };
+            let shift_decal = |decal: &Decal| Decal { position: shift(decal.position), ..decal.clone() };
+            merged.entities.extend(level.entities.iter().map(shift_entity));
+            merged.triggers.extend(level.triggers.iter().map(shift_entity));
+            merged.bg_decals.extend(level.bg_decals.iter().map(shift_decal));
+            merged.fg_decals.extend(level.fg_decals.iter().map(shift_decal));
+            merged.extra_children.extend(level.extra_children.iter().cloned());
+        }
+
+        self.levels[a] = merged;
+        self.levels.remove(b);
+        Ok(())
+    }
+}
+
+impl Map {
+    /// Finds the `n` attribute keys whose values take up the most encoded bytes across the whole map,
+    /// largest first.
+    /// 
+    /// Sizes are summed across every element with that key, and are computed with [`Value::encoded_size`].
+    /// This is useful for finding out what makes a map file large.
+    #[must_use]
+    pub fn largest_attributes(&self, n: usize) -> Vec<(String, usize)> {
+        fn add<'a>(sizes: &mut HashMap<&'a str, usize>, key: &'a str, size: usize) {
+            *sizes.entry(key).or_default() += size;
+        }
+
+        fn values<'a, K: AsRef<str>>(attributes: &'a HashMap<K, Value>, typed: &[(&'a str, usize)], sizes: &mut HashMap<&'a str, usize>) {
+            for (key, value) in attributes {
+                if !typed.iter().any(|(typed, _)| *typed == key.as_ref()) {
+                    add(sizes, key.as_ref(), value.encoded_size());
+                }
+            }
+            for &(key, size) in typed {
+                add(sizes, key, size);
+            }
+        }
+
+        fn element<'a>(el: &'a Element, sizes: &mut HashMap<&'a str, usize>) {
+            values(&el.attributes, &[], sizes);
+            for child in &el.children {
+                element(child, sizes);
+            }
+        }
+
+        let mut sizes = HashMap::new();
+        // The package is stored before the root element, not as an attribute
+        for (key, value) in self.extra_data.iter().filter(|(key, _)| key.as_str() != "_package") {
+            add(&mut sizes, key, value.encoded_size());
+        }
+        for el in self.extra_children.iter().chain(&self.foregrounds).chain(&self.backgrounds) {
+            element(el, &mut sizes);
+        }
+        for filler in &self.filler {
+            let ints = [filler.position.0, filler.position.1, filler.size.0, filler.size.1];
+            for (key, int) in ["x", "y", "w", "h"].into_iter().zip(ints) {
+                add(&mut sizes, key, Value::Integer(int).encoded_size());
+            }
+        }
+        if let Some(color) = self.bg_color {
+            add(&mut sizes, "color", 1 + string_payload_size(&color_to_hex(color), false));
+        }
+
+        // Tilemaps are only stored as strings, so one buffer is reused to measure all of them
+        let mut tiles = String::new();
+        for level in &self.levels {
+            let mut typed = level.data.attribute_sizes();
+            typed.push(("name", 1 + string_payload_size(&level.name, false)));
+            values(&level.extra_data, &typed, &mut sizes);
+            for el in &level.extra_children {
+                element(el, &mut sizes);
+            }
+
+            for entity in level.entities.iter().chain(&level.triggers) {
+                values(&entity.values, &entity.attribute_sizes(), &mut sizes);
+                for &(x, y) in &entity.nodes {
+                    add(&mut sizes, "x", Value::Float(x).encoded_size());
+                    add(&mut sizes, "y", Value::Float(y).encoded_size());
+                }
+            }
+            for decal in level.bg_decals.iter().chain(&level.fg_decals) {
+                for (key, size) in decal.attribute_sizes() {
+                    add(&mut sizes, key, size);
+                }
+            }
+
+            for (tilemap, rle) in [(&level.bg, true), (&level.solids, true)] {
+                tiles.clear();
+                tilemap.store_into(&mut tiles);
+                add(&mut sizes, "innerText", 1 + string_payload_size(&tiles, rle));
+            }
+            for tilemap in [&level.bg_tiles, &level.fg_tiles, &level.obj_tiles] {
+                tiles.clear();
+                tilemap.store_into(&mut tiles);
+                add(&mut sizes, "innerText", 1 + string_payload_size(&tiles, false));
+            }
+        }
+
+        let mut sizes: Vec<_> = sizes.into_iter().map(|(key, size)| (key.to_string(), size)).collect();
+        sizes.sort_unstable_by(|(a_key, a_size), (b_key, b_size)| b_size.cmp(a_size).then_with(|| a_key.cmp(b_key)));
+        sizes.truncate(n);
+        sizes
+    }
+}
+
+impl Map {
+    /// Gets every styleground in the map as a flat list, foregrounds first.
+    /// 
+    /// Stylegrounds can be grouped inside `apply` elements, which can themselves be nested.
+    /// This walks through those groups and returns only the stylegrounds inside them, in order.
+    #[must_use]
+    pub fn flatten_stylegrounds(&self) -> Vec<&Element> {
+        fn walk<'a>(elements: &'a [Element], out: &mut Vec<&'a Element>) {
+            for el in elements {
+                if &*el.name == "apply" {
+                    walk(&el.children, out);
+                } else {
+                    out.push(el);
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(&self.foregrounds, &mut out);
+        walk(&self.backgrounds, &mut out);
+        out
+    }
+}
+
+impl Map {
+    /// Guesses which mods this map uses, by looking at entity names and decal textures.
+    /// 
+    /// This is a heuristic:
+    /// - Modded entities and triggers are conventionally named `ModName/EntityName`,
+    ///   so anything before the first `/` in their name is counted.
+    /// - Decal textures are counted by their first folder (after an optional leading `decals/`),
+    ///   except for vanilla folders, which are either `generic` or named after a chapter like `1-forsakencity`.
+    #[must_use]
+    pub fn referenced_mod_prefixes(&self) -> BTreeSet<String> {
+        let mut prefixes = BTreeSet::new();
+        for level in &self.levels {
+            for entity in level.entities.iter().chain(&level.triggers) {
+                if let Some((prefix, _)) = entity.name.split_once('/') {
+                    if !prefix.is_empty() {
+                        prefixes.insert(prefix.to_string());
+                    }
+                }
+            }
+            for decal in level.bg_decals.iter().chain(&level.fg_decals) {
+                let texture = decal.texture.strip_prefix("decals/").unwrap_or(&decal.texture);
+                let Some((folder, _)) = texture.split_once('/') else { continue };
+                let is_chapter = folder.split_once('-')
+                    .is_some_and(|(number, _)| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()));
+                if !(folder.is_empty() || folder == "generic" || is_chapter) {
+                    prefixes.insert(folder.to_string());
+                }
+            }
+        }
+        prefixes
+    }
+
+    /// Finds entity IDs that are used more than once within a single level, counting both entities and triggers.
+    /// 
+    /// Each repeated ID is reported once as a `(level index, id)` pair, in ascending order.
+    #[must_use]
+    pub fn duplicate_entity_ids(&self) -> Vec<(usize, i32)> {
+        let mut duplicates = Vec::new();
+        for (index, level) in self.levels.iter().enumerate() {
+            let mut seen = BTreeSet::new();
+            let mut repeated = BTreeSet::new();
+            for entity in level.entities.iter().chain(&level.triggers) {
+                if !seen.insert(entity.id) {
+                    repeated.insert(entity.id);
+                }
+            }
+            duplicates.extend(repeated.into_iter().map(|id| (index, id)));
+        }
+        duplicates
+    }
+
+    /// Finds elements that are too big to be stored, returning their names in the order they'd be stored.
+    /// 
+    /// The format can only hold 255 attributes and 65535 children on a single element,
+    /// and [`Map::store`] fails partway through if either limit is hit.
+    /// Checking this first lets you fix the offending elements beforehand,
+    /// usually by moving rarely-used values off of an entity, or by splitting it into several entities.
+    #[must_use]
+    pub fn oversized_elements(&self) -> Vec<String> {
+        fn check(name: &str, attributes: usize, children: usize, out: &mut Vec<String>) {
+            if attributes > u8::MAX.into() || children > u16::MAX.into() {
+                out.push(name.to_string());
+            }
+        }
+
+        fn element(el: &Element, out: &mut Vec<String>) {
+            check(&el.name, el.attributes.len(), el.children.len(), out);
+            for child in &el.children {
+                element(child, out);
+            }
+        }
+
+        // Typed fields overwrite values with the same key when stored
+        fn attribute_count<K: AsRef<str>>(values: &HashMap<K, Value>, typed: &[(&str, usize)]) -> usize {
+            typed.len() + values.keys().filter(|key| !typed.iter().any(|(typed, _)| *typed == key.as_ref())).count()
+        }
+
+        let mut oversized = Vec::new();
+        // The package is stored before the root element, not as an attribute
+        let root_attributes = self.extra_data.keys().filter(|key| key.as_str() != "_package").count();
+        check("Map", root_attributes, self.extra_children.len() + 3, &mut oversized);
+        for el in &self.extra_children {
+            element(el, &mut oversized);
+        }
+        check("Filler", 0, self.filler.len(), &mut oversized);
+        for (name, stylegrounds) in [("Foregrounds", &self.foregrounds), ("Backgrounds", &self.backgrounds)] {
+            check(name, 0, stylegrounds.len(), &mut oversized);
+            for el in stylegrounds {
+                element(el, &mut oversized);
+            }
+        }
+
+        check("levels", 0, self.levels.len(), &mut oversized);
+        for level in &self.levels {
+            let mut typed = level.data.attribute_sizes();
+            typed.push(("name", 0));
+            // Extra children are stored alongside the four containers and five tilemaps
+            check("level", attribute_count(&level.extra_data, &typed), level.extra_children.len() + 9, &mut oversized);
+            for el in &level.extra_children {
+                element(el, &mut oversized);
+            }
+            for (container, entities) in [("entities", &level.entities), ("triggers", &level.triggers)] {
+                check(container, 0, entities.len(), &mut oversized);
+                for entity in entities {
+                    check(&entity.name, attribute_count(&entity.values, &entity.attribute_sizes()), entity.nodes.len(), &mut oversized);
+                }
+            }
+            for (container, decals) in [("bgdecals", &level.bg_decals), ("fgdecals", &level.fg_decals)] {
+                check(container, 0, decals.len(), &mut oversized);
+            }
+        }
+        oversized
+    }
+
+    /// Puts the map into a canonical form, so that two maps that hold the same content
+    /// are stored as exactly the same bytes.
+    /// 
+    /// This:
+    /// - sorts levels by name, and filler rectangles by position,
+    /// - sorts stylegrounds by name, then by their attributes and children,
+    /// - removes entity and trigger values that are overwritten by their typed fields when stored (like `x` or `id`),
+    /// - stores every run-length encoded string as a normal string, and every `-0.0` as `0.0`.
+    /// 
+    /// Note that sorting stylegrounds changes the order they're drawn in,
+    /// so this is meant for comparing and archiving maps rather than for maps that will be played.
+    pub fn canonicalize(&mut self) {
+        const TYPED_FIELDS: [&str; 7] = ["x", "y", "width", "height", "originX", "originY", "id"];
+
+        fn canonicalize_values<K>(values: &mut HashMap<K, Value>) {
+            for value in values.values_mut() {
+                match value {
+                    Value::RleString(string) => *value = Value::String(std::mem::take(string)),
+                    Value::Float(float) if *float == 0.0 => *float = 0.0,
+                    _ => ()
+                }
+            }
+        }
+
+        fn canonicalize_element(el: &mut Element) {
+            canonicalize_values(&mut el.attributes);
+            for child in &mut el.children {
+                canonicalize_element(child);
+            }
+        }
+
+        self.levels.sort_by(|a, b| a.name.cmp(&b.name));
+        self.filler.sort_unstable();
+        for stylegrounds in [&mut self.foregrounds, &mut self.backgrounds] {
+            stylegrounds.iter_mut().for_each(canonicalize_element);
+            stylegrounds.sort_by_cached_key(|el| (el.name.clone(), el.to_string()));
+        }
+        canonicalize_values(&mut self.extra_data);
+        self.extra_children.iter_mut().for_each(canonicalize_element);
+
+        for level in &mut self.levels {
+            for entity in level.entities.iter_mut().chain(&mut level.triggers) {
+                entity.values.retain(|key, _| !TYPED_FIELDS.contains(&key.as_str()));
+                canonicalize_values(&mut entity.values);
+            }
+            canonicalize_values(&mut level.extra_data);
+            level.extra_children.iter_mut().for_each(canonicalize_element);
+        }
+    }
+
+    /// Finds the level that touches the given side of `level`, like the one a player would transition into.
+    /// 
+    /// A neighbor has to share part of that edge, not just a corner.
+    /// If several levels touch that side, the one sharing the most of the edge is returned.
+    #[must_use]
+    pub fn level_in_direction(&self, level: &Level, direction: Direction) -> Option<&Level> {
+        // Edges of levels near the ends of the i32 range can be outside of it
+        let bounds = |level: &Level| {
+            let (x, y) = level.data.position;
+            let (w, h) = level.data.size;
+            (i64::from(x), i64::from(y), i64::from(w), i64::from(h))
+        };
+        let (x, y, w, h) = bounds(level);
+
+        self.levels.iter()
+            .filter(|other| !std::ptr::eq(*other, level))
+            .filter_map(|other| {
+                let (ox, oy, ow, oh) = bounds(other);
+                let (touches, overlap) = match direction {
+                    Direction::Up => (oy + oh == y, (x + w).min(ox + ow) - x.max(ox)),
+                    Direction::Down => (y + h == oy, (x + w).min(ox + ow) - x.max(ox)),
+                    Direction::Left => (ox + ow == x, (y + h).min(oy + oh) - y.max(oy)),
+                    Direction::Right => (x + w == ox, (y + h).min(oy + oh) - y.max(oy))
+                };
+                (touches && overlap > 0).then_some((other, overlap))
+            })
+            .max_by_key(|&(_, overlap)| overlap)
+            .map(|(other, _)| other)
+    }
+
+    /// Gets every string value in the map, alongside the path to where it is.
+    /// 
+    /// A path is the name of every element from the root `Map` element down to the one holding the string,
+    /// followed by the name of the attribute, as the map would be stored.
+    /// For example, a decal's texture is at `["Map", "levels", "level", "fgdecals", "decal", "texture"]`.
+    /// 
+    /// This includes strings stored in typed fields like [`Decal::texture`], not only [`Value`]s,
+    /// but not tilemaps, since those aren't strings until the map is stored.
+    #[must_use]
+    pub fn all_string_values(&self) -> Vec<(ElementPath, &str)> {
+        fn path(parents: &[&str], key: &str) -> ElementPath {
+            parents.iter().copied().chain([key]).map(String::from).collect()
+        }
+
+        fn values<'a, K: AsRef<str> + Ord>(parents: &[&str], attributes: &'a HashMap<K, Value>, out: &mut Vec<(ElementPath, &'a str)>) {
+            for (key, value) in attributes.iter().sorted_unstable_by(|(a, _), (b, _)| a.cmp(b)) {
+                if let Value::String(string) | Value::RleString(string) = value {
+                    out.push((path(parents, key.as_ref()), string));
+                }
+            }
+        }
+
+        fn element<'a>(parents: &[&str], el: &'a Element, out: &mut Vec<(ElementPath, &'a str)>) {
+            let parents = [parents, &[&*el.name]].concat();
+            values(&parents, &el.attributes, out);
+            for child in &el.children {
+                element(&parents, child, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        values(&["Map"], &self.extra_data, &mut out);
+        for child in &self.extra_children {
+            element(&["Map"], child, &mut out);
+        }
+        for styleground in &self.foregrounds {
+            element(&["Map", "Style", "Foregrounds"], styleground, &mut out);
+        }
+        for styleground in &self.backgrounds {
+            element(&["Map", "Style", "Backgrounds"], styleground, &mut out);
+        }
+
+        for level in &self.levels {
+            let parents = ["Map", "levels", "level"];
+            out.push((path(&parents, "name"), &level.name));
+            for (key, string) in [
+                ("windPattern", &level.data.wind_pattern),
+                ("alt_music", &level.data.alt_music),
+                ("ambience", &level.data.ambience),
+                ("music", &level.data.music)
+            ] {
+                out.push((path(&parents, key), string));
+            }
+            values(&parents, &level.extra_data, &mut out);
+            for child in &level.extra_children {
+                element(&parents, child, &mut out);
+            }
+
+            for (container, entities) in [("entities", &level.entities), ("triggers", &level.triggers)] {
+                for entity in entities {
+                    values(&["Map", "levels", "level", container, &entity.name], &entity.values, &mut out);
+                }
+            }
+            for (container, decals) in [("bgdecals", &level.bg_decals), ("fgdecals", &level.fg_decals)] {
+                for decal in decals {
+                    out.push((path(&["Map", "levels", "level", container, "decal"], "texture"), &decal.texture));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Replaces every occurrence of `pattern` in the map's string values with `replacement`,
+    /// returning how many strings were changed.
+    /// 
+    /// This covers the same strings as [`Map::all_string_values`],
+    /// except for `innerText` attributes, since those often hold tilemaps that would be corrupted by this.
+    /// An empty pattern doesn't replace anything.
+    pub fn replace_string_values(&mut self, pattern: &str, replacement: &str) -> usize {
+        fn replace(string: &mut String, pattern: &str, replacement: &str) -> usize {
+            if string.contains(pattern) {
+                *string = string.replace(pattern, replacement);
+                1
+            } else { 0 }
+        }
+
+        fn values<K: AsRef<str>>(attributes: &mut HashMap<K, Value>, pattern: &str, replacement: &str) -> usize {
+            attributes.iter_mut()
+                .filter(|(key, _)| key.as_ref() != "innerText")
+                .map(|(_, value)| match value {
+                    Value::String(string) | Value::RleString(string) => replace(string, pattern, replacement),
+                    _ => 0
+                })
+                .sum()
+        }
+
+        fn element(el: &mut Element, pattern: &str, replacement: &str) -> usize {
+            values(&mut el.attributes, pattern, replacement)
+                + el.children.iter_mut().map(|child| element(child, pattern, replacement)).sum::<usize>()
+        }
+
+        if pattern.is_empty() {
+            return 0;
+        }
+
+        let mut count = values(&mut self.extra_data, pattern, replacement);
+        for el in self.extra_children.iter_mut().chain(&mut self.foregrounds).chain(&mut self.backgrounds) {
+            count += element(el, pattern, replacement);
+        }
+
+        for level in &mut self.levels {
+            for string in [
+                &mut level.name,
+                &mut level.data.wind_pattern,
+                &mut level.data.alt_music,
+                &mut level.data.ambience,
+                &mut level.data.music
+            ] {
+                count += replace(string, pattern, replacement);
+            }
+            count += values(&mut level.extra_data, pattern, replacement);
+            for el in &mut level.extra_children {
+                count += element(el, pattern, replacement);
+            }
+            for entity in level.entities.iter_mut().chain(&mut level.triggers) {
+                count += values(&mut entity.values, pattern, replacement);
+            }
+            for decal in level.bg_decals.iter_mut().chain(&mut level.fg_decals) {
+                count += replace(&mut decal.texture, pattern, replacement);
+            }
+        }
+
+        count
+    }
+
+    /// Guesses which level the player starts in.
+    /// 
+    /// This is the first level with a `player` spawn point in it,
+    /// or the first level in the map if none of them have one.
+    #[must_use]
+    pub fn start_level(&self) -> Option<&Level> {
+        self.levels.iter()
+            .find(|level| level.entities.iter().any(|entity| &*entity.name == "player"))
+            .or_else(|| self.levels.first())
+    }
+
+    /// Gets the background color of the map as a hex string, like `"10203fff"`, the same way it's stored.
+    #[must_use]
+    pub fn bg_color_hex(&self) -> Option<String> {
+        self.bg_color.map(color_to_hex)
+    }
+
+    /// Sets the background color of the map from a hex string, with or without a leading `#`.
+    /// 
+    /// The string can either be 6 digits long (`RRGGBB`), or 8 digits long with an alpha channel (`RRGGBBAA`).
+    /// 
+    /// # Errors
+    /// Errors if the string isn't a valid color, in which case the color is left unchanged.
+    pub fn set_bg_color_hex(&mut self, hex: &str) -> Result<(), LoadError> {
+        self.bg_color = Some(parse_color(hex.strip_prefix('#').unwrap_or(hex).to_owned())?);
+        Ok(())
+    }
+
+    /// Sorts the extra children of the map and each of its levels by name, keeping children with the same name in order.
+    /// 
+    /// This is the same order [`Element`]'s `Display` implementation uses, and makes stored maps
+    /// not depend on the order these were added in.
+    pub fn sort_extra_children(&mut self) {
+        self.extra_children.sort_by(|a, b| a.name.cmp(&b.name));
+        for level in &mut self.levels {
+            level.extra_children.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+
+    /// Gets the total number of cells in every tilemap of every level, for estimating how much tile data a map has.
+    #[must_use]
+    pub fn total_tile_cells(&self) -> usize {
+        self.levels.iter()
+            .map(|level|
+                level.char_tilemaps().iter().map(|tilemap| tilemap.width() * tilemap.height()).sum::<usize>()
+                    + level.int_tilemaps().iter().map(|tilemap| tilemap.width() * tilemap.height()).sum::<usize>()
+            )
+            .sum()
+    }
+
+    /// Moves every filler rectangle in the map by `(dx, dy)`.
+    /// 
+    /// Like [`Filler::position`], the offset is in tiles, not pixels,
+    /// so moving the whole map by a number of pixels means dividing it by [`TILE_SIZE`] first.
+    /// 
+    /// Returns false without moving anything if any of the new positions would overflow.
+    pub fn translate_filler(&mut self, dx: i32, dy: i32) -> bool {
+        let fits = self.filler.iter()
+            .all(|filler| filler.position.0.checked_add(dx).is_some() && filler.position.1.checked_add(dy).is_some());
+        if !fits {
+            return false;
+        }
+
+        for filler in &mut self.filler {
+            filler.position.0 += dx;
+            filler.position.1 += dy;
+        }
+        true
+    }
+
+    /// Finds level names that are used by more than one level, in ascending order.
+    /// 
+    /// Celeste needs every level in a map to have a different name, and doesn't say anything when they don't.
+    #[must_use]
+    pub fn duplicate_level_names(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        let mut repeated = BTreeSet::new();
+        for level in &self.levels {
+            if !seen.insert(&*level.name) {
+                repeated.insert(&*level.name);
+            }
+        }
+        repeated.into_iter().map(str::to_owned).collect()
+    }
+
+    /// Calls `f` on every level in the map, in order.
+    pub fn for_each_level_mut(&mut self, f: impl FnMut(&mut Level)) {
+        self.levels.iter_mut().for_each(f);
+    }
+
+    /// Calls `f` on every level in the map, spread across threads with [`rayon`].
+    /// 
+    /// This gives the same results as [`Map::for_each_level_mut`], as long as `f` only touches the level it's given.
+    #[cfg(feature = "rayon")]
+    pub fn par_for_each_level_mut(&mut self, f: impl Fn(&mut Level) + Sync + Send) {
+        use rayon::iter::{IntoParallelRefMutIterator as _, ParallelIterator as _};
+        self.levels.par_iter_mut().for_each(f);
+    }
+
+    /// Replaces every level in the map with the result of calling `f` on it.
+    #[must_use]
+    pub fn map_levels(mut self, f: impl FnMut(Level) -> Level) -> Map {
+        self.levels = self.levels.into_iter().map(f).collect();
+        self
+    }
+
+    /// Builds an index from level names to their positions in [`Map::levels`], for looking levels up by name quickly.
+    /// 
+    /// If more than one level has the same name (see [`Map::duplicate_level_names`]), the first one is kept.
+    /// The index borrows the map, so it has to be rebuilt after levels are added, removed, or renamed.
+    #[must_use]
+    pub fn build_level_index(&self) -> HashMap<&str, usize> {
+        let mut index = HashMap::with_capacity(self.levels.len());
+        for (i, level) in self.levels.iter().enumerate() {
+            index.entry(&*level.name).or_insert(i);
+        }
+        index
+    }
+
+    /// Lists every entity and trigger in the map as CSV, for looking over in a spreadsheet.
+    /// 
+    /// The columns are `level,name,id,x,y,width,height`. Entities without a width or height leave those columns blank.
+    #[must_use]
+    pub fn entities_to_csv(&self) -> String {
+        fn field(s: &str) -> Cow<'_, str> {
+            if s.contains([',', '"', '\n', '\r']) {
+                Cow::Owned(format!("\"{}\"", s.replace('"', "\"\"")))
+            } else {
+                Cow::Borrowed(s)
+            }
+        }
+        let size = |v: Option<i32>| v.map(|v| v.to_string()).unwrap_or_default();
+
+        let mut csv = String::from("level,name,id,x,y,width,height\n");
+        for level in &self.levels {
+            for (_, entity) in level.all_entities() {
+                let _ = writeln!(
+                    csv, "{},{},{},{},{},{},{}",
+                    field(&level.name), field(&entity.name), entity.id,
+                    entity.position.0, entity.position.1,
+                    size(entity.width), size(entity.height)
+                );
+            }
+        }
+        csv
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 /// A filler rectangle.
@@ -26,8 +755,23 @@ pub struct Filler { // Filler
     pub size: (i32, i32), // w, h
 }
 
-#[derive(Debug, Clone, PartialEq, Default)]
+/// The path to an attribute in a map, as the names of the elements leading to it, followed by the attribute's name.
+/// 
+/// See [`Map::all_string_values`].
+pub type ElementPath = Vec<String>;
+
+/// The width and height of a tile in Celeste, in pixels.
+/// 
+/// Levels are measured in pixels, while their tilemaps are measured in tiles of this size.
+pub const TILE_SIZE: i32 = 8;
+
+#[derive(Clone, PartialEq, Default)]
 /// A single level within a map.
+/// 
+/// Whether something ends up in `entities` or `triggers` is decided only by which container it was in when loaded,
+/// not by its name, so a trigger placed in the wrong container loads as an entity (and vice versa).
+/// [`Map::load_with_warnings`](crate::Map::load_with_warnings) reports anything that looks misplaced,
+/// and [`LoadOptions::strict_entity_containers`](crate::LoadOptions::strict_entity_containers) turns it into an error.
 #[allow(missing_docs)]
 pub struct Level {
     pub name: String, // name
@@ -46,57 +790,638 @@ pub struct Level {
 }
 
 impl Level {
+    /// The largest width or height, in tiles, that a level is allowed to have.
+    /// 
+    /// Levels larger than this are rejected when creating or resizing them, rather than attempting a huge allocation.
+    /// Loading uses [`LoadOptions::max_tile_dimension`](crate::LoadOptions::max_tile_dimension) instead, which defaults to this.
+    pub const MAX_TILE_DIMENSION: usize = 1_000_000;
+
     /// Creates a new instance of a level.
     /// 
-    /// Returns [`None`] if width or height are less than 0 or cause overflow.
+    /// The width and height are in pixels, and the tilemaps are sized to match (see [`Level::tile_size`]).
+    /// 
+    /// Returns [`None`] if width or height are less than 0, are larger than [`Level::MAX_TILE_DIMENSION`] tiles, or cause overflow.
     /// 
     /// Note: Due to the original level format using `i32` for these fields, these need to as well.
     /// It's illogical, I know, but that's just how it is.
     pub fn new(name: impl Into<String>, width: i32, height: i32) -> Option<Self> {
         if !(width >= 0 && height >= 0) { return None; }
         
+        let mut level = Level {
+            name: name.into(),
+            data: LevelData {
+                size: (width, height),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (tile_width, tile_height) = level.tile_size();
+        if tile_width.max(tile_height) > Self::MAX_TILE_DIMENSION { return None; }
+        level.bg = Tilemap::new(tile_width, tile_height)?;
+        level.solids = Tilemap::new(tile_width, tile_height)?;
+        level.bg_tiles = Tilemap::new(tile_width, tile_height)?;
+        level.fg_tiles = Tilemap::new(tile_width, tile_height)?;
+        level.obj_tiles = Tilemap::new(tile_width, tile_height)?;
+
+        Some(level)
+    }
+
+    /// Creates a new level from existing tilemaps, sizing the level to fit them.
+    /// 
+    /// Returns [`None`] if the tilemaps aren't all the same size, if they're larger than [`Level::MAX_TILE_DIMENSION`] tiles,
+    /// or if they're too large for a level's pixel size to fit in an `i32`.
+    pub fn from_tilemaps(
+        name: impl Into<String>, position: (i32, i32),
+        bg: Tilemap<char>, solids: Tilemap<char>,
+        bg_tiles: Tilemap<i32>, fg_tiles: Tilemap<i32>, obj_tiles: Tilemap<i32>
+    ) -> Option<Self> {
+        let size = (bg.width(), bg.height());
+        if [(solids.width(), solids.height()), (bg_tiles.width(), bg_tiles.height()),
+            (fg_tiles.width(), fg_tiles.height()), (obj_tiles.width(), obj_tiles.height())]
+            .iter().any(|other| *other != size)
+        {
+            return None;
+        }
+        if size.0.max(size.1) > Self::MAX_TILE_DIMENSION { return None; }
+        let width = i32::try_from(size.0).ok()?.checked_mul(TILE_SIZE)?;
+        let height = i32::try_from(size.1).ok()?.checked_mul(TILE_SIZE)?;
+
         Some( Level {
             name: name.into(),
             data: LevelData {
+                position,
                 size: (width, height),
                 ..Default::default()
             },
-            bg: Tilemap::new(width as usize, height as usize)?,
-            solids: Tilemap::new(width as usize, height as usize)?,
-            bg_tiles: Tilemap::new(width as usize, height as usize)?,
-            fg_tiles: Tilemap::new(width as usize, height as usize)?,
-            obj_tiles: Tilemap::new(width as usize, height as usize)?,
+            bg, solids, bg_tiles, fg_tiles, obj_tiles,
             ..Default::default()
         } )
     }
 
     /// Resizes the Level, also resizing its tilemaps.
     /// 
-    /// Returns false if width or height are less than 0, or overflow occurs.
+    /// The width and height are in pixels, like in [`Level::new`].
+    /// 
+    /// Returns false if width or height are less than 0, are larger than [`Level::MAX_TILE_DIMENSION`] tiles, or overflow occurs.
     /// 
     /// It is **highly recommended** that you use this instead of manually resizing the internal tilemaps.
     pub fn resize(&mut self, width: i32, height: i32) -> bool {
         if width < 0 || height < 0 { return false; }
+        let old_size = std::mem::replace(&mut self.data.size, (width, height));
+        let (tile_width, tile_height) = self.tile_size();
+
+        let resized = tile_width.max(tile_height) <= Self::MAX_TILE_DIMENSION
+            && self.bg.set_width(tile_width)
+            && self.bg.set_height(tile_height)
+            && self.solids.set_width(tile_width)
+            && self.solids.set_height(tile_height)
+            && self.obj_tiles.set_width(tile_width)
+            && self.obj_tiles.set_height(tile_height)
+            && self.fg_tiles.set_width(tile_width)
+            && self.fg_tiles.set_height(tile_height)
+            && self.bg_tiles.set_width(tile_width)
+            && self.bg_tiles.set_height(tile_height);
+        if !resized {
+            self.data.size = old_size;
+        }
+        resized
+    }
+
+    /// Gets the size of the level in tiles, which is the size of its tilemaps.
+    /// 
+    /// This is the pixel size divided by [`TILE_SIZE`] and rounded down, the same as when a level is loaded.
+    /// Negative sizes count as 0.
+    #[must_use]
+    pub fn tile_size(&self) -> (usize, usize) {
+        (
+            (self.data.size.0.max(0) / TILE_SIZE).unsigned_abs() as usize,
+            (self.data.size.1.max(0) / TILE_SIZE).unsigned_abs() as usize
+        )
+    }
+
+    /// Converts a position in world pixels to the tile it's on within this level.
+    /// 
+    /// Returns [`None`] if the position is outside of the level.
+    #[must_use]
+    pub fn pixel_to_tile(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let (tile_width, tile_height) = self.tile_size();
+        let tile_x = usize::try_from((x - self.data.position.0).div_euclid(TILE_SIZE)).ok()?;
+        let tile_y = usize::try_from((y - self.data.position.1).div_euclid(TILE_SIZE)).ok()?;
+        (tile_x < tile_width && tile_y < tile_height).then_some((tile_x, tile_y))
+    }
+
+    /// Compares two levels by content, rather than exactly. See [`Map::semantic_eq`].
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Level) -> bool {
+        self.name == other.name
+            && self.data == other.data
+            && self.entities.len() == other.entities.len()
+            && self.entities.iter().zip(&other.entities).all(|(a, b)| a.semantic_eq(b))
+            && self.triggers.len() == other.triggers.len()
+            && self.triggers.iter().zip(&other.triggers).all(|(a, b)| a.semantic_eq(b))
+            && self.bg_decals == other.bg_decals
+            && self.fg_decals == other.fg_decals
+            && self.bg == other.bg
+            && self.bg_tiles == other.bg_tiles
+            && self.fg_tiles == other.fg_tiles
+            && self.obj_tiles == other.obj_tiles
+            && self.solids == other.solids
+            && attributes_semantic_eq(&self.extra_data, &other.extra_data)
+            && children_semantic_eq(&self.extra_children, &other.extra_children)
+    }
+
+    /// Normalizes the rotation of every decal in the level. See [`Decal::normalize_rotation`].
+    pub fn normalize_all_rotations(&mut self) {
+        for decal in self.bg_decals.iter_mut().chain(&mut self.fg_decals) {
+            decal.normalize_rotation();
+        }
+    }
 
-        self.bg.set_width(width as usize)
-            && self.bg.set_height(height as usize)
-            && self.solids.set_width(width as usize)
-            && self.solids.set_height(height as usize)
-            && self.obj_tiles.set_width(width as usize)
-            && self.obj_tiles.set_height(height as usize)
-            && self.fg_tiles.set_width(width as usize)
-            && self.fg_tiles.set_height(height as usize)
-            && self.bg_tiles.set_width(width as usize)
-            && self.bg_tiles.set_height(height as usize)
-            && {
-                self.data.size.0 = width;
-                self.data.size.1 = height;
+    /// Snaps every entity in the level to the grid. See [`Entity::snap_to_grid`].
+    /// 
+    /// Returns false if `grid` isn't a positive, finite number, in which case no entities are changed.
+    pub fn snap_all_entities(&mut self, grid: f32) -> bool {
+        if !(grid > 0.0 && grid.is_finite()) {
+            return false;
+        }
+        for entity in &mut self.entities {
+            entity.snap_to_grid(grid);
+        }
+        true
+    }
 
-                true
+    /// Converts entity and trigger values to the kinds a schema expects, using [`Value::coerce`].
+    /// 
+    /// The schema maps entity names to the kinds of their attributes.
+    /// Values that can't be converted, and attributes not in the schema, are left alone.
+    /// This is meant to be run after loading, since values are loaded as whatever kind they were stored as,
+    /// like a boolean stored as the integer `1`.
+    #[doc(alias = "coerce_entity_values")]
+    pub fn normalize_entity_values<S: std::hash::BuildHasher>(&mut self, schema: &HashMap<&str, HashMap<&str, ValueKind, S>, S>) {
+        for entity in self.entities.iter_mut().chain(&mut self.triggers) {
+            let Some(kinds) = schema.get(&*entity.name) else { continue };
+            for (key, value) in &mut entity.values {
+                if let Some(coerced) = kinds.get(key.as_str()).and_then(|kind| value.coerce(*kind)) {
+                    *value = coerced;
+                }
             }
+        }
+    }
+
+    /// Gets the number of entities in the level.
+    #[must_use]
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Gets the number of triggers in the level.
+    #[must_use]
+    pub fn trigger_count(&self) -> usize {
+        self.triggers.len()
+    }
+
+    /// Gets the number of background decals in the level.
+    #[must_use]
+    pub fn bg_decal_count(&self) -> usize {
+        self.bg_decals.len()
+    }
+
+    /// Gets the number of foreground decals in the level.
+    #[must_use]
+    pub fn fg_decal_count(&self) -> usize {
+        self.fg_decals.len()
+    }
+
+    /// Gets every checkpoint entity in the level, in the order they're stored.
+    #[must_use]
+    pub fn checkpoints(&self) -> Vec<&Entity> {
+        self.entities.iter()
+            .filter(|entity| &*entity.name == "checkpoint")
+            .collect()
+    }
+
+    /// Gets every checkpoint entity in the level, sorted from left to right, then top to bottom.
+    /// 
+    /// This is the order a player usually reaches them in a room that scrolls horizontally.
+    #[must_use]
+    pub fn checkpoints_by_position(&self) -> Vec<&Entity> {
+        let mut checkpoints = self.checkpoints();
+        checkpoints.sort_by(|a, b|
+            a.position.0.total_cmp(&b.position.0)
+                .then_with(|| a.position.1.total_cmp(&b.position.1))
+        );
+        checkpoints
+    }
+
+    /// Checks whether the level contains a checkpoint.
+    #[must_use]
+    pub fn has_checkpoint(&self) -> bool {
+        self.entities.iter().any(|entity| &*entity.name == "checkpoint")
+    }
+
+    /// Gets every entity with the given name that doesn't have `attr` in its values.
+    /// 
+    /// This is useful for finding entities that are missing configuration they need to work in-game.
+    /// Only [`Entity::values`] is checked, so typed fields like `width` can't be looked for this way.
+    #[must_use]
+    pub fn entities_missing_attr(&self, name: &str, attr: &str) -> Vec<&Entity> {
+        self.entities.iter()
+            .filter(|entity| entity.name == name && !entity.values.contains_key(attr))
+            .collect()
+    }
+
+    /// Gets the character tilemaps of the level, as `[bg, solids]`.
+    #[must_use]
+    pub fn char_tilemaps(&self) -> [&Tilemap<char>; 2] {
+        [&self.bg, &self.solids]
+    }
+
+    /// Gets mutable references to the character tilemaps of the level, as `[bg, solids]`.
+    pub fn char_tilemaps_mut(&mut self) -> [&mut Tilemap<char>; 2] {
+        [&mut self.bg, &mut self.solids]
+    }
+
+    /// Gets the integer tilemaps of the level, as `[bg_tiles, fg_tiles, obj_tiles]`.
+    #[must_use]
+    pub fn int_tilemaps(&self) -> [&Tilemap<i32>; 3] {
+        [&self.bg_tiles, &self.fg_tiles, &self.obj_tiles]
+    }
+
+    /// Gets mutable references to the integer tilemaps of the level, as `[bg_tiles, fg_tiles, obj_tiles]`.
+    pub fn int_tilemaps_mut(&mut self) -> [&mut Tilemap<i32>; 3] {
+        [&mut self.bg_tiles, &mut self.fg_tiles, &mut self.obj_tiles]
+    }
+
+    /// Gets the fraction of the solids layer that isn't empty, from `0.0` to `1.0`.
+    /// 
+    /// Levels with no tiles at all return `0.0`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn solid_fill_ratio(&self) -> f32 {
+        let total = self.solids.raw_data().len();
+        if total == 0 {
+            return 0.0;
+        }
+        self.solids.non_empty_count() as f32 / total as f32
+    }
+
+    /// Finds the containers in this level that have too many children to be stored, by their element names.
+    /// 
+    /// The format can only hold 65535 children in one element, so a level with more entities, triggers,
+    /// or decals than that can't be stored. See also [`Map::oversized_elements`].
+    #[must_use]
+    pub fn oversized_entity_lists(&self) -> Vec<&'static str> {
+        let max = usize::from(u16::MAX);
+        [
+            ("entities", self.entities.len()),
+            ("triggers", self.triggers.len()),
+            ("bgdecals", self.bg_decals.len()),
+            ("fgdecals", self.fg_decals.len())
+        ].into_iter()
+            .filter(|&(_, len)| len > max)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Gets every entity whose position is within `radius` pixels of `(x, y)`, including ones exactly on the edge.
+    /// 
+    /// Only the position of each entity is checked, not its size or nodes.
+    #[must_use]
+    pub fn entities_near(&self, x: f32, y: f32, radius: f32) -> Vec<&Entity> {
+        self.entities.iter()
+            .filter(|entity| (entity.position.0 - x).hypot(entity.position.1 - y) <= radius)
+            .collect()
+    }
+
+    /// Gets every distinct decal depth in the level, across both the background and foreground, in order.
+    #[must_use]
+    pub fn decal_depths(&self) -> BTreeSet<i32> {
+        self.bg_decals.iter().chain(&self.fg_decals).map(|decal| decal.depth).collect()
+    }
+
+    /// Reads a boolean flag from [`Level::extra_data`], for flags that mods add to levels.
+    /// 
+    /// These live on the level rather than its [`LevelData`], since that's where unknown attributes end up.
+    /// Returns [`None`] if the flag isn't there or can't be read as a boolean.
+    #[must_use]
+    pub fn flag(&self, key: &str) -> Option<bool> {
+        match self.extra_data.get(key)?.coerce(ValueKind::Boolean)? {
+            Value::Boolean(flag) => Some(flag),
+            _ => None
+        }
+    }
+
+    /// Sets a boolean flag in [`Level::extra_data`]. See [`Level::flag`].
+    pub fn set_flag(&mut self, key: impl Into<String>, value: bool) {
+        self.extra_data.insert(key.into(), Value::Boolean(value));
+    }
+
+    /// Creates an empty level with the same size and settings as this one, for making new rooms from a template.
+    /// 
+    /// The new level keeps this level's [`LevelData`] (including its position, so it'll overlap this one until it's moved)
+    /// and [`Level::extra_data`], but has no entities, triggers, decals, or extra children,
+    /// and all of its tilemaps are empty.
+    #[must_use]
+    pub fn blank_like(&self, name: impl Into<String>) -> Level {
+        macro_rules! blank { ($tilemap: expr) => {
+            Tilemap::with_empty($tilemap.width(), $tilemap.height(), $tilemap.empty())
+                .expect("an existing tilemap's size should be valid")
+        } }
+
+        Level {
+            name: name.into(),
+            data: self.data.clone(),
+            bg: blank!(self.bg),
+            bg_tiles: blank!(self.bg_tiles),
+            fg_tiles: blank!(self.fg_tiles),
+            obj_tiles: blank!(self.obj_tiles),
+            solids: blank!(self.solids),
+            extra_data: self.extra_data.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Gets a summary of what's in the level, for tools that show an overview of a map.
+    /// 
+    /// This is the same as calling the individual accessors, like [`Level::entity_count`] and [`Level::solid_fill_ratio`].
+    #[must_use]
+    pub fn stats(&self) -> LevelStats {
+        LevelStats {
+            entities: self.entity_count(),
+            triggers: self.trigger_count(),
+            bg_decals: self.bg_decal_count(),
+            fg_decals: self.fg_decal_count(),
+            solid_fill_ratio: self.solid_fill_ratio(),
+            tile_size: self.tile_size()
+        }
+    }
+
+    /// Computes where the camera starts when the player spawns at `spawn`, as the top-left corner of the screen.
+    /// 
+    /// Both positions are relative to the level, like [`Entity::position`].
+    /// This follows Celeste's camera math: the screen (320x180 pixels) is centered on the spawn,
+    /// moved by [`LevelData::camera_offset`] (in units of 48 pixels horizontally and 32 pixels vertically),
+    /// and then kept inside of the level. Camera triggers and other entities that move the camera aren't accounted for.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn camera_target(&self, spawn: (f32, f32)) -> (f32, f32) {
+        let (offset_x, offset_y) = self.data.camera_offset;
+        let x = spawn.0 - 160.0 + 48.0 * offset_x as f32;
+        let y = spawn.1 - 90.0 + 32.0 * offset_y as f32;
+        // Same order as XNA's clamp, so levels smaller than the screen are pinned to their top-left corner
+        (
+            x.min(self.data.size.0 as f32 - 320.0).max(0.0),
+            y.min(self.data.size.1 as f32 - 180.0).max(0.0)
+        )
+    }
+
+    /// Gets every entity that matches a predicate, in the order they're stored.
+    #[must_use]
+    pub fn entities_where(&self, pred: impl Fn(&Entity) -> bool) -> Vec<&Entity> {
+        self.entities.iter().filter(|entity| pred(entity)).collect()
+    }
+
+    /// Gets every entity that has `key` in its values, set to `value`.
+    /// 
+    /// Values are compared with [`Value::semantic_eq`], so it doesn't matter whether strings are run-length encoded.
+    /// Only [`Entity::values`] is checked, like with [`Level::entities_missing_attr`].
+    #[must_use]
+    pub fn entities_with_attr(&self, key: &str, value: &Value) -> Vec<&Entity> {
+        self.entities_where(|entity| entity.values.get(key).is_some_and(|other| other.semantic_eq(value)))
+    }
+
+    /// Gets either the entities or the triggers of the level.
+    #[must_use]
+    pub fn entities_of(&self, kind: EntityKind) -> &[Entity] {
+        match kind {
+            EntityKind::Entity => &self.entities,
+            EntityKind::Trigger => &self.triggers
+        }
+    }
+
+    /// Gets either the entities or the triggers of the level, mutably.
+    pub fn entities_of_mut(&mut self, kind: EntityKind) -> &mut Vec<Entity> {
+        match kind {
+            EntityKind::Entity => &mut self.entities,
+            EntityKind::Trigger => &mut self.triggers
+        }
+    }
+
+    /// Adds an entity to the entities or triggers of the level, going by its [`Entity::kind`].
+    pub fn push_entity(&mut self, entity: Entity) {
+        self.entities_of_mut(entity.kind).push(entity);
+    }
+
+    /// Iterates over both the entities and triggers of the level, along with which of the two each one is.
+    /// 
+    /// Entities come first, then triggers.
+    pub fn all_entities(&self) -> impl Iterator<Item = (EntityKind, &Entity)> {
+        self.entities.iter().map(|entity| (EntityKind::Entity, entity))
+            .chain(self.triggers.iter().map(|trigger| (EntityKind::Trigger, trigger)))
+    }
+
+    /// Finds the tilemap layers that are entirely empty, by their element names.
+    /// 
+    /// Layers are checked in the order they're stored: `bg`, `bgtiles`, `fgtiles`, `objtiles`, then `solids`.
+    #[must_use]
+    pub fn empty_layers(&self) -> Vec<&'static str> {
+        [
+            ("bg", self.bg.is_empty()),
+            ("bgtiles", self.bg_tiles.is_empty()),
+            ("fgtiles", self.fg_tiles.is_empty()),
+            ("objtiles", self.obj_tiles.is_empty()),
+            ("solids", self.solids.is_empty())
+        ].into_iter()
+            .filter_map(|(name, empty)| empty.then_some(name))
+            .collect()
+    }
+
+    /// Copies the entity at `index` in [`Level::entities`], moving the copy (and its nodes) by `offset`.
+    /// 
+    /// The copy gets an id one higher than the highest id of any entity or trigger in the level, so it doesn't clash.
+    /// Returns the index of the copy, or [`None`] if there's no entity at `index` or the ids have run out.
+    pub fn duplicate_entity(&mut self, index: usize, offset: (f32, f32)) -> Option<usize> {
+        let id = self.entities.iter().chain(&self.triggers)
+            .map(|entity| entity.id)
+            .max()
+            .map_or(Some(0), |max| max.checked_add(1))?;
+
+        let mut copy = self.entities.get(index)?.clone();
+        copy.id = id;
+        copy.position = (copy.position.0 + offset.0, copy.position.1 + offset.1);
+        for node in &mut copy.nodes {
+            *node = (node.0 + offset.0, node.1 + offset.1);
+        }
+        self.entities.push(copy);
+        Some(self.entities.len() - 1)
+    }
+
+    /// Rounds the position of every decal in the level to whole pixels. See [`Decal::round_position`].
+    pub fn round_all_decal_positions(&mut self) {
+        for decal in self.bg_decals.iter_mut().chain(&mut self.fg_decals) {
+            decal.round_position();
+        }
+    }
+
+    /// Hashes the contents of the level, for spotting rooms that are copies of each other.
+    /// 
+    /// Only the tilemaps, entities, and triggers are hashed. Entity ids and order don't matter,
+    /// and neither does the level's name or position, so a room pasted elsewhere has the same fingerprint.
+    /// 
+    /// This is 64-bit FNV-1a over a fixed little-endian encoding of those fields,
+    /// so fingerprints are the same across runs, platforms, and Rust versions, and can be saved for later.
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        fn tilemap<T: TilemapCell>(hasher: &mut Fnv1a, tilemap: &Tilemap<T>, bytes: impl Fn(T) -> [u8; 4]) {
+            hasher.write_len(tilemap.width());
+            hasher.write_len(tilemap.height());
+            hasher.write(&bytes(tilemap.empty()));
+            for cell in tilemap.raw_data() {
+                hasher.write(&bytes(*cell));
+            }
+        }
+
+        fn entity_hash(entity: &Entity) -> u64 {
+            let mut hasher = Fnv1a::new();
+            hasher.write_str(&entity.name);
+            hasher.write_len(entity.nodes.len());
+            for (x, y) in iter::once(entity.position).chain(iter::once(entity.origin)).chain(entity.nodes.iter().copied()) {
+                hasher.write(&x.to_bits().to_le_bytes());
+                hasher.write(&y.to_bits().to_le_bytes());
+            }
+            for size in [entity.width, entity.height] {
+                match size {
+                    Some(size) => {
+                        hasher.write(&[1]);
+                        hasher.write(&size.to_le_bytes());
+                    },
+                    None => hasher.write(&[0])
+                }
+            }
+            hasher.write_len(entity.values.len());
+            for (key, value) in entity.values.iter().sorted_by_key(|(key, _)| *key) {
+                hasher.write_str(key);
+                match value {
+                    Value::Boolean(bool) => hasher.write(&[0, u8::from(*bool)]),
+                    Value::Integer(int) => {
+                        hasher.write(&[1]);
+                        hasher.write(&int.to_le_bytes());
+                    },
+                    Value::Float(float) => {
+                        hasher.write(&[2]);
+                        hasher.write(&float.to_bits().to_le_bytes());
+                    },
+                    Value::String(string) => {
+                        hasher.write(&[3]);
+                        hasher.write_str(string);
+                    },
+                    Value::RleString(string) => {
+                        hasher.write(&[4]);
+                        hasher.write_str(string);
+                    },
+                    Value::Unknown(tag, bytes) => {
+                        hasher.write(&[5, *tag]);
+                        hasher.write_len(bytes.len());
+                        hasher.write(bytes);
+                    }
+                }
+            }
+            hasher.finish()
+        }
+
+        let mut hasher = Fnv1a::new();
+        tilemap(&mut hasher, &self.bg, |cell| u32::from(cell).to_le_bytes());
+        tilemap(&mut hasher, &self.bg_tiles, i32::to_le_bytes);
+        tilemap(&mut hasher, &self.fg_tiles, i32::to_le_bytes);
+        tilemap(&mut hasher, &self.obj_tiles, i32::to_le_bytes);
+        tilemap(&mut hasher, &self.solids, |cell| u32::from(cell).to_le_bytes());
+        for entities in [&self.entities, &self.triggers] {
+            let mut entity_hashes = entities.iter().map(entity_hash).collect_vec();
+            entity_hashes.sort_unstable();
+            hasher.write_len(entity_hashes.len());
+            for hash in entity_hashes {
+                hasher.write(&hash.to_le_bytes());
+            }
+        }
+        hasher.finish()
+    }
+}
+
+/// 64-bit FNV-1a, for hashes that need to stay the same everywhere. See [`Level::fingerprint`].
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    /// Lengths are written as `u64`s, so they're the same size on every platform.
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, string: &str) {
+        self.write_len(string.len());
+        self.write(string.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Level {
+    /// Formats the level like a derived `Debug` would, except tilemaps are shown by their size,
+    /// since printing every tile of a map makes the output far too long to read.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        macro_rules! tilemap { ($tilemap: expr) => {
+            format_args!("Tilemap {}x{}", $tilemap.width(), $tilemap.height())
+        } }
+
+        f.debug_struct("Level")
+            .field("name", &self.name)
+            .field("data", &self.data)
+            .field("entities", &self.entities)
+            .field("triggers", &self.triggers)
+            .field("bg_decals", &self.bg_decals)
+            .field("fg_decals", &self.fg_decals)
+            .field("bg", &tilemap!(self.bg))
+            .field("bg_tiles", &tilemap!(self.bg_tiles))
+            .field("fg_tiles", &tilemap!(self.fg_tiles))
+            .field("obj_tiles", &tilemap!(self.obj_tiles))
+            .field("solids", &tilemap!(self.solids))
+            .field("extra_data", &self.extra_data)
+            .field("extra_children", &self.extra_children)
+            .finish()
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+/// A summary of the contents of a [`Level`]. See [`Level::stats`].
+pub struct LevelStats {
+    /// The number of entities in the level.
+    pub entities: usize,
+    /// The number of triggers in the level.
+    pub triggers: usize,
+    /// The number of background decals in the level.
+    pub bg_decals: usize,
+    /// The number of foreground decals in the level.
+    pub fg_decals: usize,
+    /// The fraction of the solids layer that isn't empty, from `0.0` to `1.0`.
+    pub solid_fill_ratio: f32,
+    /// The width and height of the level in tiles.
+    pub tile_size: (usize, usize)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 /// A struct that holds the (large amount of) metadata within a [`Level`].
 #[allow(missing_docs)]
@@ -121,18 +1446,245 @@ pub struct LevelData {
     pub whisper: bool, // whisper
 }
 
+impl LevelData {
+    /// Gets the key and encoded size of every attribute this is stored as, without storing it.
+    fn attribute_sizes(&self) -> Vec<(&'static str, usize)> {
+        let int = |int: i32| Value::Integer(int).encoded_size();
+        let string = |str: &str| 1 + string_payload_size(str, false);
+        let progress = |progress: Option<i32>| progress.map_or_else(|| string(""), |progress| string(&progress.to_string()));
+        let bool = Value::Boolean(false).encoded_size();
+
+        let mut sizes = vec![
+            ("x", int(self.position.0)),
+            ("y", int(self.position.1)),
+            ("width", int(self.size.0)),
+            ("height", int(self.size.1)),
+            ("musicLayer1", bool),
+            ("musicLayer2", bool),
+            ("musicLayer3", bool),
+            ("musicLayer4", bool),
+            ("underwater", bool),
+            ("space", bool),
+            ("disableDownTransition", bool),
+            ("musicProgress", progress(self.music_progress)),
+            ("cameraOffsetX", int(self.camera_offset.0)),
+            ("cameraOffsetY", int(self.camera_offset.1)),
+            ("windPattern", string(&self.wind_pattern)),
+            ("ambienceProgress", progress(self.ambience_progress)),
+            ("alt_music", string(&self.alt_music)),
+            ("ambience", string(&self.ambience)),
+            ("delayAltMusicFade", bool),
+            ("music", string(&self.music)),
+            ("c", int(self.color)),
+            ("dark", bool),
+            ("whisper", bool)
+        ];
+        sizes.extend(self.enforce_dash_number.map(|number| ("enforceDashNumber", int(number))));
+        sizes
+    }
+
+    /// Parses the level's wind pattern into a [`WindPattern`].
+    /// 
+    /// The raw string is kept in [`LevelData::wind_pattern`] so that patterns
+    /// this crate doesn't know about still survive a round trip.
+    /// 
+    /// # Errors
+    /// Errors if the stored string isn't a known wind pattern.
+    pub fn wind_pattern_parsed(&self) -> Result<WindPattern, LoadError> {
+        self.wind_pattern.parse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+/// A wind pattern that Celeste accepts for [`LevelData::wind_pattern`].
+#[allow(missing_docs)]
+pub enum WindPattern {
+    #[default]
+    None,
+    Left,
+    Right,
+    LeftStrong,
+    RightStrong,
+    LeftOnOff,
+    RightOnOff,
+    LeftOnOffFast,
+    RightOnOffFast,
+    Alternating,
+    LeftGemsOnly,
+    RightCrazy,
+    Down,
+    Up,
+    Space
+}
+
+impl WindPattern {
+    /// Every wind pattern, in the order Celeste defines them.
+    pub const ALL: [WindPattern; 15] = [
+        Self::None, Self::Left, Self::Right, Self::LeftStrong, Self::RightStrong,
+        Self::LeftOnOff, Self::RightOnOff, Self::LeftOnOffFast, Self::RightOnOffFast,
+        Self::Alternating, Self::LeftGemsOnly, Self::RightCrazy, Self::Down, Self::Up, Self::Space
+    ];
+
+    /// Gets the name of this wind pattern as Celeste writes it.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::LeftStrong => "LeftStrong",
+            Self::RightStrong => "RightStrong",
+            Self::LeftOnOff => "LeftOnOff",
+            Self::RightOnOff => "RightOnOff",
+            Self::LeftOnOffFast => "LeftOnOffFast",
+            Self::RightOnOffFast => "RightOnOffFast",
+            Self::Alternating => "Alternating",
+            Self::LeftGemsOnly => "LeftGemsOnly",
+            Self::RightCrazy => "RightCrazy",
+            Self::Down => "Down",
+            Self::Up => "Up",
+            Self::Space => "Space"
+        }
+    }
+}
+
+impl fmt::Display for WindPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WindPattern {
+    type Err = LoadError;
+
+    /// Parses a wind pattern, ignoring ASCII case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|pattern| pattern.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| LoadError::InvalidFieldData("windPattern", s.to_string()))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 /// An entity inside a [`Level`].
+/// 
+/// Triggers are entities too. [`Entity::kind`] says which of the two it is, and is set when loading
+/// by which container of the level it came from, so it stays with the entity when it's taken out of the level.
+/// When storing, only the container it's in matters; [`Level::push_entity`] puts it in the one matching its kind.
 #[allow(missing_docs)]
 pub struct Entity {
     pub name: String, // element name
     pub id: i32, // id
     pub position: (f32, f32), // x, y
     pub width: Option<i32>, // width
-    pub height: Option<i32>, // width
+    pub height: Option<i32>, // height
     pub origin: (f32, f32), // originX, originY
     pub nodes: Vec<(f32, f32)>, // children (with name "node")
-    pub values: HashMap<String, Value>
+    pub values: HashMap<String, Value>,
+    pub kind: EntityKind // which container it was loaded from (not stored)
+}
+
+impl Entity {
+    /// Gets the key and encoded size of every typed field this is stored with, without storing it.
+    fn attribute_sizes(&self) -> Vec<(&'static str, usize)> {
+        let int = |int: i32| Value::Integer(int).encoded_size();
+        let float = |float: f32| Value::Float(float).encoded_size();
+
+        let mut sizes = vec![
+            ("x", float(self.position.0)),
+            ("y", float(self.position.1)),
+            ("originX", float(self.origin.0)),
+            ("originY", float(self.origin.1)),
+            ("id", int(self.id))
+        ];
+        sizes.extend(self.width.map(|width| ("width", int(width))));
+        sizes.extend(self.height.map(|height| ("height", int(height))));
+        sizes
+    }
+
+    /// Sets the size of the entity.
+    /// 
+    /// Returns false if width or height are negative, in which case the size is left unchanged.
+    pub fn set_size(&mut self, width: Option<i32>, height: Option<i32>) -> bool {
+        if width.is_some_and(i32::is_negative) || height.is_some_and(i32::is_negative) {
+            return false;
+        }
+
+        self.width = width;
+        self.height = height;
+        true
+    }
+
+    /// Rounds the entity's position and nodes to the nearest multiple of `grid`.
+    /// 
+    /// Celeste's tiles are [`TILE_SIZE`] pixels wide, so a `grid` of `8.0` aligns the entity to tiles.
+    /// 
+    /// Returns false if `grid` isn't a positive, finite number, in which case the entity is left unchanged.
+    pub fn snap_to_grid(&mut self, grid: f32) -> bool {
+        if !(grid > 0.0 && grid.is_finite()) {
+            return false;
+        }
+        let snap = |v: f32| (v / grid).round() * grid;
+        self.position = (snap(self.position.0), snap(self.position.1));
+        for node in &mut self.nodes {
+            *node = (snap(node.0), snap(node.1));
+        }
+        true
+    }
+
+    /// Compares two entities by content, comparing their values with [`Value::semantic_eq`].
+    #[must_use]
+    pub fn semantic_eq(&self, other: &Entity) -> bool {
+        self.name == other.name
+            && self.id == other.id
+            && self.position == other.position
+            && self.width == other.width
+            && self.height == other.height
+            && self.origin == other.origin
+            && self.nodes == other.nodes
+            && attributes_semantic_eq(&self.values, &other.values)
+    }
+
+    /// Gets the entity's nodes relative to its position.
+    /// 
+    /// Nodes are stored as absolute coordinates in the level, which is what Celeste expects.
+    #[must_use]
+    pub fn relative_nodes(&self) -> Vec<(f32, f32)> {
+        self.nodes.iter()
+            .map(|&(x, y)| (x - self.position.0, y - self.position.1))
+            .collect()
+    }
+
+    /// Sets the entity's nodes from coordinates relative to its position.
+    pub fn set_relative_nodes(&mut self, nodes: impl IntoIterator<Item = (f32, f32)>) {
+        let (x, y) = self.position;
+        self.nodes = nodes.into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .collect();
+    }
+
+    /// Guesses whether this is a trigger from its name.
+    /// 
+    /// Nearly every trigger, vanilla or modded, has a name ending in `Trigger` (like `windTrigger` or `MyMod/FlagTrigger`).
+    /// The few vanilla triggers that don't are special-cased.
+    /// Entities that only start with it, like `triggerSpikesUp`, aren't triggers.
+    #[must_use]
+    pub fn looks_like_trigger(&self) -> bool {
+        is_trigger_name(&self.name)
+    }
+}
+
+/// See [`Entity::looks_like_trigger`].
+pub(crate) fn is_trigger_name(name: &str) -> bool {
+    const UNSUFFIXED_TRIGGERS: [&str; 2] = ["lookoutBlocker", "blackholeStrength"];
+
+    const SUFFIX: &str = "trigger";
+
+    let unprefixed = name.rsplit_once('/').map_or(name, |(_, name)| name);
+    unprefixed.len().checked_sub(SUFFIX.len())
+        .and_then(|start| unprefixed.get(start..))
+        .is_some_and(|suffix| suffix.eq_ignore_ascii_case(SUFFIX))
+        || UNSUFFIXED_TRIGGERS.contains(&name)
 }
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -146,3 +1698,96 @@ pub struct Decal {
     pub depth: i32, // depth
     pub rotation: f32 // rotation
 }
+
+impl Decal {
+    /// Gets the key and encoded size of every attribute this is stored as, without storing it.
+    fn attribute_sizes(&self) -> Vec<(&'static str, usize)> {
+        let float = |float: f32| Value::Float(float).encoded_size();
+
+        let mut sizes = vec![
+            ("x", float(self.position.0)),
+            ("y", float(self.position.1)),
+            ("scaleX", float(self.scale.0)),
+            ("scaleY", float(self.scale.1)),
+            ("texture", 1 + string_payload_size(&self.texture, false))
+        ];
+        if self.rotation != 0.0 {
+            sizes.push(("rotation", float(self.rotation)));
+        }
+        if self.depth != 0 {
+            sizes.push(("depth", Value::Integer(self.depth).encoded_size()));
+        }
+        if self.color != [0xFF; 4] {
+            sizes.push(("color", 1 + string_payload_size(&color_to_hex(self.color), false)));
+        }
+        sizes
+    }
+
+    /// Reduces the decal's rotation into the range `[0, 360)`.
+    /// 
+    /// Celeste treats rotations modulo 360, so this doesn't change how the decal looks.
+    pub fn normalize_rotation(&mut self) {
+        let rotation = self.rotation.rem_euclid(360.0);
+        // rem_euclid can round up to exactly 360 for tiny negative inputs
+        self.rotation = if rotation >= 360.0 { 0.0 } else { rotation };
+    }
+
+    /// Flips the decal horizontally, by negating its horizontal scale.
+    pub fn flip_x(&mut self) {
+        self.scale.0 = -self.scale.0;
+    }
+
+    /// Flips the decal vertically, by negating its vertical scale.
+    pub fn flip_y(&mut self) {
+        self.scale.1 = -self.scale.1;
+    }
+
+    /// Checks whether the decal is flipped horizontally, meaning its horizontal scale is negative.
+    #[must_use]
+    pub fn is_flipped_x(&self) -> bool {
+        self.scale.0.is_sign_negative()
+    }
+
+    /// Checks whether the decal is flipped vertically, meaning its vertical scale is negative.
+    #[must_use]
+    pub fn is_flipped_y(&self) -> bool {
+        self.scale.1.is_sign_negative()
+    }
+
+    /// Rounds the decal's position to the nearest whole pixel, with halves rounding away from zero.
+    pub fn round_position(&mut self) {
+        self.position = (self.position.0.round(), self.position.1.round());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A side of a [`Level`], used for finding its neighbors.
+#[allow(missing_docs)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+/// Whether an [`Entity`] is an entity or a trigger, going by which container of its [`Level`] it's in.
+/// 
+/// Both are stored the same way, so they share a type. See [`Entity::kind`] and [`Level::all_entities`].
+#[allow(missing_docs)]
+pub enum EntityKind {
+    #[default]
+    Entity,
+    Trigger
+}
+
+impl EntityKind {
+    /// Gets the name of the element that holds this kind of entity in a level.
+    #[must_use]
+    pub fn container_name(self) -> &'static str {
+        match self {
+            Self::Entity => "entities",
+            Self::Trigger => "triggers"
+        }
+    }
+}
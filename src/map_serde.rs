@@ -1,9 +1,10 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io, sync::Arc};
 
+use indexmap::IndexSet;
 use itertools::Itertools as _;
 
 use crate::{
-    Decal, Element, Entity, Filler, Level, LevelData, LoadError, Map, Tilemap, Value
+    Decal, Element, Entity, EntityKind, Filler, Level, LevelData, LoadError, LoadOptions, LoadWarning, Map, Tilemap, Value, ValueKind, TILE_SIZE
 };
 
 // So.
@@ -53,6 +54,10 @@ macro_rules! remove_as {
                 res
             } else if let Value::Integer(res) = field {
                 res as f32
+            } else if let Value::String(res) | Value::RleString(res) = field {
+                // Same as integers, some third-party maps store floats as strings.
+                // Celeste can't store NaN or infinity, so those fall back to the default too.
+                res.trim().parse().ok().filter(|float: &f32| float.is_finite()).unwrap_or($default)
             } else {
                 return Err(LoadError::InvalidFieldType($field_name, field));
             }
@@ -66,6 +71,27 @@ macro_rules! remove_as {
                 res
             } else if let Value::Float(res) = field {
                 res as i32
+            } else if let Value::String(res) | Value::RleString(res) = field {
+                // Some third-party maps store integers as strings
+                res.trim().parse().unwrap_or($default)
+            } else {
+                return Err(LoadError::InvalidFieldType($field_name, field));
+            }
+        } else {
+            $default
+        }
+    };
+    ($el: ident [ $field_name: literal ]: Boolean or $default: expr) => {
+        if let Some(field) = $el.attributes.remove($field_name) {
+            if let Value::Boolean(res) = field {
+                res
+            } else if let Value::String(res) | Value::RleString(res) = field {
+                // Some maps store booleans as strings too
+                match res.trim() {
+                    res if res.eq_ignore_ascii_case("true") => true,
+                    res if res.eq_ignore_ascii_case("false") => false,
+                    _ => $default
+                }
             } else {
                 return Err(LoadError::InvalidFieldType($field_name, field));
             }
@@ -101,7 +127,7 @@ macro_rules! remove_as {
 #[macro_export]
 macro_rules! attributes {
     ($($name: literal $(if $guard: expr)? => $expr: expr),*) => {{
-        let mut map = HashMap::<String, Value>::new();
+        let mut map = HashMap::<std::sync::Arc<str>, Value>::new();
         $(
             attributes!(_single map $name $(if $guard)? => $expr);
         )*
@@ -117,25 +143,42 @@ macro_rules! attributes {
     }
 }
 
+/// Converts the leftover attributes of an element into the [`String`] keys that typed structs keep them with.
+pub(crate) fn owned_keys(attributes: HashMap<Arc<str>, Value>) -> HashMap<String, Value> {
+    attributes.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+}
+
+/// Converts values kept by a typed struct back into attributes. See [`owned_keys`].
+pub(crate) fn shared_keys(values: HashMap<String, Value>) -> HashMap<Arc<str>, Value> {
+    values.into_iter().map(|(key, value)| (key.into(), value)).collect()
+}
+
 macro_rules! check_name {
     ($ident: ident is $name: literal) => {
-        if ($ident.name != $name) {
-            return Err(LoadError::InvalidElementName($ident.name, $name));
+        if (&*$ident.name != $name) {
+            return Err(LoadError::InvalidElementName($ident.name.to_string(), $name));
         }
     };
 }
 
-fn parse_color(color_string: String) -> Result<[u8; 4], LoadError> {
+pub(crate) fn parse_color(color_string: String) -> Result<[u8; 4], LoadError> {
+    // Hex digits are all ASCII, so after this check every byte is a whole character
     if !matches!(color_string.len(), 6 | 8)
-        || color_string.chars().any(|c| !c.is_ascii_hexdigit())
+        || !color_string.bytes().all(|byte| byte.is_ascii_hexdigit())
     {
         return Err(LoadError::InvalidFieldData("color", color_string));
     }
 
+    // Anything missing (like the alpha of a 6 digit color) stays opaque.
+    // The length check above means there are never more than 4 pairs, so zipping doesn't drop any.
     let mut color_buf = [0xFF_u8; 4];
-    for (i, chunk) in color_string.chars().chunks(2).into_iter().enumerate() {
-        let byte = chunk.collect::<String>();
-        color_buf[i] = u8::from_str_radix(&byte, 16).expect("hex string was already validated")
+    for (channel, pair) in color_buf.iter_mut().zip(color_string.as_bytes().chunks_exact(2)) {
+        let digit = |byte: u8| match byte {
+            b'0' ..= b'9' => byte - b'0',
+            b'a' ..= b'f' => byte - b'a' + 10,
+            _ => byte - b'A' + 10
+        };
+        *channel = digit(pair[0]) << 4 | digit(pair[1]);
     }
 
     Ok(color_buf)
@@ -177,11 +220,7 @@ impl From<Decal> for Element {
                 "texture" => value.texture,
                 "rotation" if value.rotation != 0.0 => value.rotation,
                 "depth" if value.depth != 0 => value.depth,
-                "color" if value.color != [0xFF; 4] =>
-                    value.color
-                        .into_iter()
-                        .map(|v| format!("{v:02x}"))
-                        .join("")
+                "color" if value.color != [0xFF; 4] => value.color
             },
             children: vec![],
         }
@@ -222,7 +261,7 @@ impl From<Filler> for Element {
 }
 
 impl LevelData {
-    fn load_from(value: &mut Element) -> Result<Self, LoadError> {
+    pub(crate) fn load_from(value: &mut Element) -> Result<Self, LoadError> {
         let music_progress_str = remove_as!(value["musicProgress"]: String or String::new());
         let music_progress: Option<i32> = (!music_progress_str.is_empty())
             .then(|| music_progress_str.parse())
@@ -241,8 +280,8 @@ impl LevelData {
                 remove_as!(value["y"]: Integer or 0)
             ),
             size: (
-                remove_as!(value["width"]: Integer or 8), // Celeste has 8-pixel tiles
-                remove_as!(value["height"]: Integer or 8)
+                remove_as!(value["width"]: Integer or TILE_SIZE),
+                remove_as!(value["height"]: Integer or TILE_SIZE)
             ),
             music_layers: [
                 remove_as!(value["musicLayer1"]: Boolean or false),
@@ -271,7 +310,7 @@ impl LevelData {
         })
     }
 
-    fn store_to(self, el: &mut Element) {
+    pub(crate) fn store_to(self, el: &mut Element) {
         el.attributes.extend(attributes! {
             "x" => self.position.0,
             "y" => self.position.1,
@@ -328,17 +367,25 @@ impl TryFrom<Element> for Entity {
             }).collect::<Result<_, _>>()?;
 
         Ok(Self {
-            name: value.name,
+            name: value.name.to_string(),
             id, position, width, height, origin,
             nodes,
-            values: value.attributes // The other ones were already removed from remove_as!()
+            values: owned_keys(value.attributes), // The other ones were already removed from remove_as!()
+            kind: EntityKind::Entity
         })
     }
 }
 
+impl Entity {
+    /// Converts an element from a level's `triggers` into an [`Entity`] with the trigger kind.
+    pub(crate) fn trigger_from(value: Element) -> Result<Self, LoadError> {
+        Ok(Self { kind: EntityKind::Trigger, ..Self::try_from(value)? })
+    }
+}
+
 impl From<Entity> for Element {
     fn from(value: Entity) -> Element {
-        let mut attrs = value.values;
+        let mut attrs = shared_keys(value.values);
         attrs.extend(attributes! {
             "x" => value.position.0,
             "y" => value.position.1,
@@ -350,7 +397,7 @@ impl From<Entity> for Element {
         });
 
         Element {
-            name: value.name,
+            name: value.name.into(),
             attributes: attrs,
             children: value.nodes.into_iter().map(|(x, y)| Element {
                 name: "node".into(),
@@ -364,20 +411,154 @@ impl From<Entity> for Element {
     }
 }
 
+/// Collects everything that looks wrong in a freshly loaded map.
+pub(crate) fn load_warnings(map: &Map) -> Vec<LoadWarning> {
+    map.levels.iter()
+        .flat_map(|level| {
+            let entities = level.entities.iter().filter(|entity| entity.looks_like_trigger()).map(|entity| (entity, false));
+            let triggers = level.triggers.iter().filter(|trigger| !trigger.looks_like_trigger()).map(|trigger| (trigger, true));
+            let misplaced = entities.chain(triggers).map(|(entity, in_triggers)| LoadWarning::MisplacedEntity {
+                level: level.name.clone(),
+                name: entity.name.clone(),
+                id: entity.id,
+                in_triggers
+            });
+            let negative = level.entities.iter().chain(&level.triggers)
+                .filter(|entity| entity.width.is_some_and(i32::is_negative) || entity.height.is_some_and(i32::is_negative))
+                .map(|entity| LoadWarning::NegativeEntitySize {
+                    level: level.name.clone(),
+                    name: entity.name.clone(),
+                    id: entity.id
+                });
+            misplaced.chain(negative)
+        })
+        .collect()
+}
+
+/// Fails if any entity or trigger was warned about being in the wrong container,
+/// listing all of them.
+pub(crate) fn check_entity_containers(warnings: &[LoadWarning]) -> Result<(), LoadError> {
+    let mut misplaced = warnings.iter()
+        .filter(|warning| matches!(warning, LoadWarning::MisplacedEntity { .. }))
+        .peekable();
+    let Some(LoadWarning::MisplacedEntity { in_triggers, .. }) = misplaced.peek() else {
+        return Ok(());
+    };
+    let field = if *in_triggers { "triggers" } else { "entities" };
+    Err(LoadError::InvalidFieldData(field, misplaced.join("; ")))
+}
+
+/// Grows the `width` and `height` of every level in a map's root element
+/// to fit the largest tilemap inside of it.
+/// 
+/// Tilemaps are sized by the level's dimensions when loading, and anything past them is dropped,
+/// so this keeps those tiles around for levels with wrong dimensions.
+pub(crate) fn expand_level_sizes(root: &mut Element) {
+    let levels = root.children.iter_mut()
+        .filter(|child| &*child.name == "levels")
+        .flat_map(|levels| levels.children.iter_mut())
+        .filter(|child| &*child.name == "level");
+
+    for level in levels {
+        let (mut tile_width, mut tile_height) = (0, 0);
+        for tilemap in &level.children {
+            let is_char = match &*tilemap.name {
+                "bg" | "solids" => true,
+                "bgtiles" | "fgtiles" | "objtiles" => false,
+                _ => continue
+            };
+            let Some(text) = tilemap.inner_text() else { continue };
+            for (y, line) in text.lines().enumerate() {
+                let width = if line.is_empty() { 0 }
+                    else if is_char { line.chars().count() }
+                    else { line.split(',').count() };
+                if width > 0 {
+                    tile_width = tile_width.max(width);
+                    tile_height = tile_height.max(y + 1);
+                }
+            }
+        }
+
+        for (key, tiles) in [("width", tile_width), ("height", tile_height)] {
+            let declared = level.attributes.get(key)
+                .and_then(|value| value.coerce(ValueKind::Integer))
+                .map_or(0, |value| if let Value::Integer(int) = value { int } else { 0 });
+            let needed = i32::try_from(tiles).unwrap_or(i32::MAX).saturating_mul(TILE_SIZE);
+            if needed > declared {
+                level.attributes.insert(key.into(), Value::Integer(needed));
+            }
+        }
+    }
+}
+
+/// Removes level attributes that are the same as what they'd load as if they were missing,
+/// the way Celeste leaves them out of its own maps.
+/// 
+/// The position and size of a level are always kept.
+pub(crate) fn omit_level_defaults(root: &mut Element) {
+    let defaults = [
+        ("musicLayer1", Value::Boolean(false)),
+        ("musicLayer2", Value::Boolean(false)),
+        ("musicLayer3", Value::Boolean(false)),
+        ("musicLayer4", Value::Boolean(false)),
+        ("underwater", Value::Boolean(false)),
+        ("space", Value::Boolean(false)),
+        ("disableDownTransition", Value::Boolean(false)),
+        ("musicProgress", Value::String(String::new())),
+        ("cameraOffsetX", Value::Integer(0)),
+        ("cameraOffsetY", Value::Integer(0)),
+        ("windPattern", Value::String("None".into())),
+        ("ambienceProgress", Value::String(String::new())),
+        ("alt_music", Value::String(String::new())),
+        ("ambience", Value::String(String::new())),
+        ("delayAltMusicFade", Value::Boolean(false)),
+        ("music", Value::String(String::new())),
+        ("c", Value::Integer(0)),
+        ("dark", Value::Boolean(false)),
+        ("whisper", Value::Boolean(false))
+    ];
+
+    let levels = root.children.iter_mut()
+        .filter(|child| &*child.name == "levels")
+        .flat_map(|levels| levels.children.iter_mut())
+        .filter(|child| &*child.name == "level");
+
+    for level in levels {
+        for (key, default) in &defaults {
+            if level.attributes.get(*key) == Some(default) {
+                level.attributes.remove(*key);
+            }
+        }
+    }
+}
+
 impl TryFrom<Element> for Level {
     type Error = LoadError;
 
-    fn try_from(mut value: Element) -> Result<Self, Self::Error> {
+    fn try_from(value: Element) -> Result<Self, Self::Error> {
+        Level::from_element(value, LoadOptions::default())
+    }
+}
+
+impl Level {
+    /// Converts a `level` element into a [`Level`], using the parts of `options` that affect levels.
+    pub(crate) fn from_element(mut value: Element, options: LoadOptions) -> Result<Self, LoadError> {
         check_name!(value is "level");
         let data = LevelData::load_from(&mut value)?;
         if data.size.0 < 0 {
             return Err(LoadError::InvalidFieldData("width", "width cannot be negative".into()))
         }
-        if data.size.0 < 0 {
+        if data.size.1 < 0 {
             return Err(LoadError::InvalidFieldData("height", "height cannot be negative".into()))
         }
-        let tile_width = (data.size.0 / 8) as usize;
-        let tile_height = (data.size.1 / 8) as usize;
+        let tile_width = (data.size.0 / TILE_SIZE) as usize;
+        let tile_height = (data.size.1 / TILE_SIZE) as usize;
+        if tile_width > options.max_tile_dimension {
+            return Err(LoadError::InvalidFieldData("width", format!("width is larger than {} tiles", options.max_tile_dimension)))
+        }
+        if tile_height > options.max_tile_dimension {
+            return Err(LoadError::InvalidFieldData("height", format!("height is larger than {} tiles", options.max_tile_dimension)))
+        }
         let name = remove_as!(value["name"]: String or "<unnamed>".into());
         
         let mut entities = vec![];
@@ -385,25 +566,26 @@ impl TryFrom<Element> for Level {
         let mut bg_decals = vec![];
         let mut fg_decals = vec![];
 
-        let mut bg = Tilemap::new(tile_width, tile_height).ok_or(
+        let (empty_char, empty_int) = (options.empty_char_tile, options.empty_int_tile);
+        let mut bg = Tilemap::with_empty(tile_width, tile_height, empty_char).ok_or(
             LoadError::InvalidFieldData("bg", "tilemap size is too large for this machine to store in memory".into())
         )?;
         // Past this point we unwrap because we know the tilemap fits
-        let mut bg_tiles = Tilemap::new(tile_width, tile_height).unwrap();
-        let mut fg_tiles = Tilemap::new(tile_width, tile_height).unwrap();
-        let mut obj_tiles = Tilemap::new(tile_width, tile_height).unwrap();
-        let mut solids = Tilemap::new(tile_width, tile_height).unwrap();
+        let mut bg_tiles = Tilemap::with_empty(tile_width, tile_height, empty_int).unwrap();
+        let mut fg_tiles = Tilemap::with_empty(tile_width, tile_height, empty_int).unwrap();
+        let mut obj_tiles = Tilemap::with_empty(tile_width, tile_height, empty_int).unwrap();
+        let mut solids = Tilemap::with_empty(tile_width, tile_height, empty_char).unwrap();
 
         let mut extra_children = Vec::new();
         for mut child in value.children {
-            match child.name.as_str() {
+            match &*child.name {
                 "entities" => 
                     entities = child.children.into_iter()
                         .map(Entity::try_from)
                         .collect::<Result<_, _>>()?,
                 "triggers" => 
                     triggers = child.children.into_iter()
-                        .map(Entity::try_from)
+                        .map(Entity::trigger_from)
                         .collect::<Result<_, _>>()?,
                 "bgdecals" => 
                     bg_decals = child.children.into_iter()
@@ -414,25 +596,25 @@ impl TryFrom<Element> for Level {
                         .map(Decal::try_from)
                         .collect::<Result<_, _>>()?,
                 "bg" => 
-                    bg = Tilemap::<char>::load(
-                        remove_as!(child["innerText"]: String or String::new()), 
-                        tile_width, tile_height
+                    bg = Tilemap::<char>::load_with_empty(
+                        &remove_as!(child["innerText"]: String or String::new()), 
+                        tile_width, tile_height, empty_char
                     ).unwrap(),
-                "bgtiles" => bg_tiles = Tilemap::<i32>::load(
-                    remove_as!(child["innerText"]: String or String::new()), 
-                    tile_width, tile_height
+                "bgtiles" => bg_tiles = Tilemap::<i32>::load_with_empty(
+                    &remove_as!(child["innerText"]: String or String::new()), 
+                    tile_width, tile_height, empty_int
                 ).unwrap(),
-                "fgtiles" => fg_tiles = Tilemap::<i32>::load(
-                    remove_as!(child["innerText"]: String or String::new()), 
-                    tile_width, tile_height
+                "fgtiles" => fg_tiles = Tilemap::<i32>::load_with_empty(
+                    &remove_as!(child["innerText"]: String or String::new()), 
+                    tile_width, tile_height, empty_int
                 ).unwrap(),
-                "solids" => solids = Tilemap::<char>::load(
-                    remove_as!(child["innerText"]: String or String::new()), 
-                    tile_width, tile_height
+                "solids" => solids = Tilemap::<char>::load_with_empty(
+                    &remove_as!(child["innerText"]: String or String::new()), 
+                    tile_width, tile_height, empty_char
                 ).unwrap(),
-                "objtiles" => obj_tiles = Tilemap::<i32>::load(
-                    remove_as!(child["innerText"]: String or String::new()), 
-                    tile_width, tile_height
+                "objtiles" => obj_tiles = Tilemap::<i32>::load_with_empty(
+                    &remove_as!(child["innerText"]: String or String::new()), 
+                    tile_width, tile_height, empty_int
                 ).unwrap(),
                 _ => extra_children.push(child)
             }
@@ -442,79 +624,153 @@ impl TryFrom<Element> for Level {
             name, data, entities, triggers, bg_decals, fg_decals,
             bg, bg_tiles, fg_tiles, obj_tiles, solids,
             extra_children, 
-            extra_data: value.attributes
+            extra_data: owned_keys(value.attributes)
         })
     }
 }
 
-impl From<Level> for Element {
-    fn from(value: Level) -> Self {
+/// A level's tilemaps, kept apart from the rest of its element so they can be stored one at a time.
+pub(crate) struct LevelTilemaps {
+    bg: Tilemap<char>,
+    bg_tiles: Tilemap<i32>,
+    fg_tiles: Tilemap<i32>,
+    obj_tiles: Tilemap<i32>,
+    solids: Tilemap<char>
+}
+
+impl LevelTilemaps {
+    /// How many tilemaps a level has. They're always the last children of its element.
+    pub(crate) const COUNT: usize = 5;
+
+    /// Adds the strings these tilemaps would put in the lookup table, in the order they'd be encoded.
+    /// 
+    /// Only the tilemaps that aren't run-length encoded can end up in the table, so the others aren't stored.
+    pub(crate) fn collect_strings(&self, buf: &mut String, lookup: &mut IndexSet<Arc<str>>) {
+        let mut collect = |name: &str, tilemap: Option<&Tilemap<i32>>| {
+            Value::collect_str(name, lookup);
+            Value::collect_str("innerText", lookup);
+            if let Some(tilemap) = tilemap {
+                buf.clear();
+                tilemap.store_into(buf);
+                Value::collect_str(buf, lookup);
+            }
+        };
+        collect("bg", None);
+        collect("bgtiles", Some(&self.bg_tiles));
+        collect("fgtiles", Some(&self.fg_tiles));
+        collect("objtiles", Some(&self.obj_tiles));
+        collect("solids", None);
+    }
+
+    /// Encodes these tilemaps as elements, storing each one into the same buffer.
+    pub(crate) fn encode(&self, buf: &mut String, stream: &mut dyn io::Write, lookup: &mut IndexSet<Arc<str>>) -> io::Result<()> {
+        buf.clear();
+        self.bg.store_into(buf);
+        Element::encode_text("bg", "innerText", buf, true, stream, lookup)?;
+        buf.clear();
+        self.bg_tiles.store_into(buf);
+        Element::encode_text("bgtiles", "innerText", buf, false, stream, lookup)?;
+        buf.clear();
+        self.fg_tiles.store_into(buf);
+        Element::encode_text("fgtiles", "innerText", buf, false, stream, lookup)?;
+        buf.clear();
+        self.obj_tiles.store_into(buf);
+        Element::encode_text("objtiles", "innerText", buf, false, stream, lookup)?;
+        buf.clear();
+        self.solids.store_into(buf);
+        Element::encode_text("solids", "innerText", buf, true, stream, lookup)
+    }
+
+    fn into_elements(self) -> [Element; Self::COUNT] {
+        [
+            Element {
+                name: "bg".into(),
+                attributes: attributes!{ "innerText" => Value::RleString(self.bg.store()) },
+                children: vec![]
+            },
+            Element {
+                name: "bgtiles".into(),
+                attributes: attributes!{ "innerText" => self.bg_tiles.store() },
+                children: vec![]
+            },
+            Element {
+                name: "fgtiles".into(),
+                attributes: attributes!{ "innerText" => self.fg_tiles.store() },
+                children: vec![]
+            },
+            Element {
+                name: "objtiles".into(),
+                attributes: attributes!{ "innerText" => self.obj_tiles.store() },
+                children: vec![]
+            },
+            Element {
+                name: "solids".into(),
+                attributes: attributes!{ "innerText" => Value::RleString(self.solids.store()) },
+                children: vec![]
+            },
+        ]
+    }
+}
+
+impl Level {
+    /// Converts this level into its element, leaving out the tilemaps so they can be stored separately.
+    pub(crate) fn into_element_without_tilemaps(self) -> (Element, LevelTilemaps) {
         let mut el = Element {
             name: "level".into(),
-            attributes: value.extra_data,
-            children: value.extra_children
+            attributes: shared_keys(self.extra_data),
+            children: self.extra_children
         };
-        value.data.store_to(&mut el);
+        self.data.store_to(&mut el);
         el.attributes.extend(attributes! {
-            "name" => value.name
+            "name" => self.name
         });
 
         el.children.extend([
             Element {
                 name: "entities".into(),
                 attributes: HashMap::new(),
-                children: value.entities.into_iter()
+                children: self.entities.into_iter()
                     .map(Into::into)
                     .collect()
             },
             Element {
                 name: "triggers".into(),
                 attributes: HashMap::new(),
-                children: value.triggers.into_iter()
+                children: self.triggers.into_iter()
                     .map(Into::into)
                     .collect()
             },
             Element {
                 name: "bgdecals".into(),
                 attributes: HashMap::new(),
-                children: value.bg_decals.into_iter()
+                children: self.bg_decals.into_iter()
                     .map(Into::into)
                     .collect()
             },
             Element {
                 name: "fgdecals".into(),
                 attributes: HashMap::new(),
-                children: value.fg_decals.into_iter()
+                children: self.fg_decals.into_iter()
                     .map(Into::into)
                     .collect()
             },
-            Element {
-                name: "bg".into(),
-                attributes: attributes!{ "innerText" => Value::RleString(value.bg.store()) },
-                children: vec![]
-            },
-            Element {
-                name: "bgtiles".into(),
-                attributes: attributes!{ "innerText" => value.bg_tiles.store() },
-                children: vec![]
-            },
-            Element {
-                name: "fgtiles".into(),
-                attributes: attributes!{ "innerText" => value.fg_tiles.store() },
-                children: vec![]
-            },
-            Element {
-                name: "objtiles".into(),
-                attributes: attributes!{ "innerText" => value.obj_tiles.store() },
-                children: vec![]
-            },
-            Element {
-                name: "solids".into(),
-                attributes: attributes!{ "innerText" => Value::RleString(value.solids.store()) },
-                children: vec![]
-            },
         ]);
 
+        let tilemaps = LevelTilemaps {
+            bg: self.bg,
+            bg_tiles: self.bg_tiles,
+            fg_tiles: self.fg_tiles,
+            obj_tiles: self.obj_tiles,
+            solids: self.solids
+        };
+        (el, tilemaps)
+    }
+}
+
+impl From<Level> for Element {
+    fn from(value: Level) -> Self {
+        let (mut el, tilemaps) = value.into_element_without_tilemaps();
+        el.children.extend(tilemaps.into_elements());
         el
     }
 }
@@ -522,7 +778,14 @@ impl From<Level> for Element {
 impl TryFrom<Element> for Map {
     type Error = LoadError;
 
-    fn try_from(mut value: Element) -> Result<Self, Self::Error> {
+    fn try_from(value: Element) -> Result<Self, Self::Error> {
+        Map::from_element(value, LoadOptions::default())
+    }
+}
+
+impl Map {
+    /// Converts a root element into a [`Map`], using the parts of `options` that affect the map's contents.
+    pub(crate) fn from_element(mut value: Element, options: LoadOptions) -> Result<Self, LoadError> {
         check_name!(value is "Map");
         
         let package = remove_as!(value["_package"]: String or String::new());
@@ -534,8 +797,14 @@ impl TryFrom<Element> for Map {
 
         let mut extra_children = Vec::new();
 
-        for mut child in value.children {
-            match child.name.as_str() {
+        // Maps should only have one Style, but if there's more, only the last one is read into the typed fields.
+        // The others are kept in extra_children, which are stored before the typed Style,
+        // so the same one stays typed across a round trip.
+        let last_style = value.children.iter().rposition(|child| &*child.name == "Style");
+
+        for (i, mut child) in value.children.into_iter().enumerate() {
+            match &*child.name {
+                "Style" if Some(i) != last_style => extra_children.push(child),
                 "Filler" => 
                     filler = child.children.into_iter()
                         .map(Filler::try_from)
@@ -545,7 +814,7 @@ impl TryFrom<Element> for Map {
                         .map(parse_color)
                         .transpose()?;
                     for grandchild in child.children {
-                        match grandchild.name.as_str() {
+                        match &*grandchild.name {
                             "Foregrounds" =>
                                 foregrounds = grandchild.children,
                             "Backgrounds" =>
@@ -556,7 +825,7 @@ impl TryFrom<Element> for Map {
                 },
                 "levels" =>
                     levels = child.children.into_iter()
-                    .map(Level::try_from)
+                    .map(|level| Level::from_element(level, options))
                     .collect::<Result<_, _>>()?,
                 _ => extra_children.push(child)
             }
@@ -564,7 +833,8 @@ impl TryFrom<Element> for Map {
 
         Ok(Map {
             package, filler, levels, foregrounds, backgrounds, 
-            bg_color, extra_data: value.attributes, extra_children
+            bg_color, extra_data: owned_keys(value.attributes), extra_children,
+            header: None
         })
     }
 }
@@ -572,7 +842,7 @@ impl TryFrom<Element> for Map {
 impl From<Map> for Element {
     fn from(value: Map) -> Self {
         let mut children = value.extra_children;
-        let mut attributes = value.extra_data;
+        let mut attributes = shared_keys(value.extra_data);
         attributes.insert("_package".into(), value.package.into());
 
         children.push(Element {
@@ -588,12 +858,7 @@ impl From<Map> for Element {
             attributes: {
                 let mut attrs = HashMap::new();
                 if let Some(col) = value.bg_color {
-                    attrs.insert("color".into(), col
-                        .into_iter()
-                        .map(|v| format!("{v:02x}"))
-                        .join("")
-                        .into()
-                    );
+                    attrs.insert("color".into(), Value::from_color(col));
                 }
                 attrs
             },
@@ -0,0 +1,106 @@
+/// Options that change how a map is loaded. See [`Map::load_with_options`](crate::Map::load_with_options).
+///
+/// The default options behave exactly like [`Map::load`](crate::Map::load) with `check_header` set to `true`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::struct_excessive_bools)] // They're all independent switches
+pub struct LoadOptions {
+    /// Whether to check that the map starts with the `CELESTE MAP` header.
+    pub check_header: bool,
+    /// Whether to keep values with unknown type tags as [`Value::Unknown`](crate::Value::Unknown)
+    /// instead of failing with [`LoadError::InvalidValueType`](crate::LoadError::InvalidValueType).
+    ///
+    /// There's no way to know how long a value of an unknown type is,
+    /// so these are assumed to be a variable-length integer holding the length, followed by that many bytes,
+    /// the same way strings are stored. If that's wrong, loading will most likely fail later on anyways.
+    pub lenient_values: bool,
+    /// Whether to grow levels to fit their tilemaps.
+    ///
+    /// Tilemaps are sized by their level's `width` and `height`, and any tiles outside of that are dropped.
+    /// With this on, levels whose tilemaps are bigger than they say they are get their size increased to fit,
+    /// so no tiles are lost.
+    pub expand_tilemaps: bool,
+    /// Whether to check that triggers are in a level's `triggers` and entities are in its `entities`.
+    ///
+    /// Normally, the container alone decides which is which, and anything that looks misplaced
+    /// (going by [`Entity::looks_like_trigger`](crate::Entity::looks_like_trigger)) is only reported as a
+    /// [`LoadWarning::MisplacedEntity`](crate::LoadWarning::MisplacedEntity) by [`Map::load_with_warnings`](crate::Map::load_with_warnings).
+    /// With this on, loading fails with [`LoadError::InvalidFieldData`](crate::LoadError::InvalidFieldData) listing all of them instead.
+    pub strict_entity_containers: bool,
+    /// Whether to check that nothing comes after the map in the stream.
+    /// 
+    /// Normally, loading stops right after the map and leaves anything else in the stream alone.
+    /// With this on, the rest of the stream is read, and loading fails with
+    /// [`LoadError::TrailingData`](crate::LoadError::TrailingData) if it isn't empty.
+    pub reject_trailing_data: bool,
+    /// Whether to load strings that aren't valid UTF-8 anyways, replacing the invalid parts with `U+FFFD`.
+    /// 
+    /// Normally, these fail with [`LoadError::IoError`](crate::LoadError::IoError). Turning this on lets maps saved by
+    /// tools that mangled their text encoding still load, but those strings won't be stored back as they were.
+    pub lossy_utf8: bool,
+    /// The sentinel that marks a cell as empty in character tilemaps (`bg` and `solids`).
+    /// 
+    /// Celeste uses `'0'`, but maps that use it as a real tile can pick something else.
+    /// Missing cells are filled with this, and it's kept as the loaded tilemaps' [`Tilemap::empty`](crate::Tilemap::empty),
+    /// so storing the map trims the same cells back off.
+    pub empty_char_tile: char,
+    /// The sentinel that marks a cell as empty in integer tilemaps (`bgtiles`, `fgtiles`, and `objtiles`).
+    /// 
+    /// Celeste uses `-1`. Like [`LoadOptions::empty_char_tile`], but cells that can't be parsed are also filled with this.
+    pub empty_int_tile: i32,
+    /// The largest width or height, in tiles, that a loaded level is allowed to have.
+    /// 
+    /// Levels larger than this fail with [`LoadError::InvalidFieldData`](crate::LoadError::InvalidFieldData)
+    /// rather than attempting a huge allocation.
+    /// Defaults to [`Level::MAX_TILE_DIMENSION`](crate::Level::MAX_TILE_DIMENSION).
+    pub max_tile_dimension: usize
+}
+
+/// Options that change how a map is stored. See [`Map::store_with_options`](crate::Map::store_with_options).
+///
+/// The default options behave exactly like [`Map::store`](crate::Map::store) with `write_header` set to `true`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::struct_excessive_bools)] // Same as LoadOptions
+pub struct StoreOptions {
+    /// Whether to write the `CELESTE MAP` header.
+    pub write_header: bool,
+    /// Whether to replace NaN and infinite floats with `0.0`
+    /// instead of failing with [`StoreError::NonFiniteFloat`](crate::StoreError::NonFiniteFloat).
+    ///
+    /// Celeste can't load maps with these, so they're never written.
+    pub clamp_non_finite_floats: bool,
+    /// Whether to sort extra children by name before storing, so that their order doesn't change the output.
+    /// See [`Map::sort_extra_children`](crate::Map::sort_extra_children).
+    pub sort_extra_children: bool,
+    /// Whether to leave out level attributes that are set to their defaults, like `underwater` being `false`.
+    ///
+    /// Celeste leaves these out of its own maps, so this makes stored maps smaller and closer to the originals.
+    /// They load back the same either way.
+    pub omit_defaults: bool
+}
+
+impl Default for StoreOptions {
+    fn default() -> Self {
+        Self {
+            write_header: true,
+            clamp_non_finite_floats: false,
+            sort_extra_children: false,
+            omit_defaults: false
+        }
+    }
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            check_header: true,
+            lenient_values: false,
+            expand_tilemaps: false,
+            strict_entity_containers: false,
+            reject_trailing_data: false,
+            lossy_utf8: false,
+            empty_char_tile: '0',
+            empty_int_tile: -1,
+            max_tile_dimension: crate::Level::MAX_TILE_DIMENSION
+        }
+    }
+}
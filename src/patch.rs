@@ -0,0 +1,545 @@
+use std::{collections::{HashMap, HashSet}, io};
+
+use itertools::Itertools as _;
+
+use crate::{
+    ext::ReadExt as _,
+    map_serde::{owned_keys, shared_keys},
+    tilemap::TilemapCell,
+    Decal, Element, Entity, EntityKind, Level, LevelData, LoadError, LoadOptions, Map, StoreError, TilemapPatch, Value
+};
+
+/// A set of changes that turns one [`Map`] into another.
+///
+/// Levels are matched up by name, and entities and triggers by ID,
+/// so that only what actually changed needs to be stored.
+/// If names or IDs aren't unique, the affected list is stored in full instead.
+///
+/// See [`Map::create_patch`] and [`Map::apply_patch`].
+/// Patches can be stored and loaded on their own with [`MapPatch::store`] and [`MapPatch::load`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapPatch {
+    /// Everything in the new map other than its levels, if any of it changed.
+    ///
+    /// The levels of this map are always empty.
+    pub rest: Option<Map>,
+    /// The changes to the map's levels, if any changed.
+    pub levels: Option<LevelListPatch>
+}
+
+/// A set of changes to a list of levels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LevelListPatch {
+    /// Levels were changed, added, removed, or reordered.
+    Edit {
+        /// The names of every level in the new map, in order.
+        order: Vec<String>,
+        /// Levels that weren't in the old map.
+        added: Vec<Level>,
+        /// Changes to levels that were in both maps.
+        changed: Vec<LevelPatch>
+    },
+    /// The levels couldn't be matched up by name, so they were replaced entirely.
+    Replace(Vec<Level>)
+}
+
+/// A set of changes to a single [`Level`]. Fields that didn't change are [`None`].
+#[derive(Debug, Clone, PartialEq, Default)]
+#[allow(missing_docs)]
+pub struct LevelPatch {
+    /// The name of the level being changed.
+    pub name: String,
+    pub data: Option<LevelData>,
+    pub entities: Option<EntityListPatch>,
+    pub triggers: Option<EntityListPatch>,
+    pub bg_decals: Option<Vec<Decal>>,
+    pub fg_decals: Option<Vec<Decal>>,
+    pub bg: Option<TilemapPatch<char>>,
+    pub bg_tiles: Option<TilemapPatch<i32>>,
+    pub fg_tiles: Option<TilemapPatch<i32>>,
+    pub obj_tiles: Option<TilemapPatch<i32>>,
+    pub solids: Option<TilemapPatch<char>>,
+    pub extra_data: Option<HashMap<String, Value>>,
+    pub extra_children: Option<Vec<Element>>
+}
+
+/// A set of changes to a list of entities or triggers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityListPatch {
+    /// Entities were changed, added, removed, or reordered.
+    Edit {
+        /// The IDs of every entity in the new list, in order.
+        order: Vec<i32>,
+        /// Entities that were added or changed.
+        upserted: Vec<Entity>
+    },
+    /// The entities couldn't be matched up by ID, so they were replaced entirely.
+    Replace(Vec<Entity>)
+}
+
+/// Checks that every key in the list is unique.
+fn all_unique<'a, K: Eq + std::hash::Hash + 'a>(keys: impl IntoIterator<Item = &'a K>) -> bool {
+    let mut seen = HashSet::new();
+    keys.into_iter().all(|key| seen.insert(key))
+}
+
+/// Only keeps a field if it changed.
+fn changed<T: PartialEq + Clone>(old: &T, new: &T) -> Option<T> {
+    (old != new).then(|| new.clone())
+}
+
+impl EntityListPatch {
+    fn create(old: &[Entity], new: &[Entity]) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        if !(all_unique(old.iter().map(|e| &e.id)) && all_unique(new.iter().map(|e| &e.id))) {
+            return Some(EntityListPatch::Replace(new.to_vec()));
+        }
+
+        let old_by_id: HashMap<_, _> = old.iter().map(|e| (e.id, e)).collect();
+        Some(EntityListPatch::Edit {
+            order: new.iter().map(|e| e.id).collect(),
+            upserted: new.iter()
+                .filter(|e| old_by_id.get(&e.id) != Some(e))
+                .cloned()
+                .collect()
+        })
+    }
+
+    fn apply(&self, entities: &mut Vec<Entity>) {
+        match self {
+            EntityListPatch::Replace(new) => entities.clone_from(new),
+            EntityListPatch::Edit { order, upserted } => {
+                let mut by_id: HashMap<_, _> = entities.drain(..).map(|e| (e.id, e)).collect();
+                by_id.extend(upserted.iter().map(|e| (e.id, e.clone())));
+                entities.extend(order.iter().filter_map(|id| by_id.remove(id)));
+            }
+        }
+    }
+}
+
+impl LevelPatch {
+    fn create(old: &Level, new: &Level) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        Some(LevelPatch {
+            name: new.name.clone(),
+            data: changed(&old.data, &new.data),
+            entities: EntityListPatch::create(&old.entities, &new.entities),
+            triggers: EntityListPatch::create(&old.triggers, &new.triggers),
+            bg_decals: changed(&old.bg_decals, &new.bg_decals),
+            fg_decals: changed(&old.fg_decals, &new.fg_decals),
+            bg: TilemapPatch::create(&old.bg, &new.bg),
+            bg_tiles: TilemapPatch::create(&old.bg_tiles, &new.bg_tiles),
+            fg_tiles: TilemapPatch::create(&old.fg_tiles, &new.fg_tiles),
+            obj_tiles: TilemapPatch::create(&old.obj_tiles, &new.obj_tiles),
+            solids: TilemapPatch::create(&old.solids, &new.solids),
+            extra_data: changed(&old.extra_data, &new.extra_data),
+            extra_children: changed(&old.extra_children, &new.extra_children)
+        })
+    }
+
+    /// Applies this patch to a level.
+    pub fn apply(&self, level: &mut Level) {
+        macro_rules! replace {
+            ($($field: ident),*) => {$(
+                if let Some(value) = &self.$field {
+                    level.$field.clone_from(value);
+                }
+            )*};
+        }
+
+        replace!(data, bg_decals, fg_decals, extra_data, extra_children);
+        for (patch, tilemap) in [(&self.bg, &mut level.bg), (&self.solids, &mut level.solids)] {
+            if let Some(patch) = patch {
+                patch.apply(tilemap);
+            }
+        }
+        for (patch, tilemap) in [
+            (&self.bg_tiles, &mut level.bg_tiles),
+            (&self.fg_tiles, &mut level.fg_tiles),
+            (&self.obj_tiles, &mut level.obj_tiles)
+        ] {
+            if let Some(patch) = patch {
+                patch.apply(tilemap);
+            }
+        }
+        if let Some(patch) = &self.entities {
+            patch.apply(&mut level.entities);
+        }
+        if let Some(patch) = &self.triggers {
+            patch.apply(&mut level.triggers);
+        }
+    }
+}
+
+impl LevelListPatch {
+    fn create(old: &[Level], new: &[Level]) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        if !(all_unique(old.iter().map(|l| &l.name)) && all_unique(new.iter().map(|l| &l.name))) {
+            return Some(LevelListPatch::Replace(new.to_vec()));
+        }
+
+        let old_by_name: HashMap<_, _> = old.iter().map(|l| (&*l.name, l)).collect();
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for level in new {
+            match old_by_name.get(&*level.name) {
+                None => added.push(level.clone()),
+                Some(old) => changed.extend(LevelPatch::create(old, level))
+            }
+        }
+
+        Some(LevelListPatch::Edit {
+            order: new.iter().map(|l| l.name.clone()).collect(),
+            added, changed
+        })
+    }
+
+    fn apply(&self, levels: &mut Vec<Level>) {
+        match self {
+            LevelListPatch::Replace(new) => levels.clone_from(new),
+            LevelListPatch::Edit { order, added, changed } => {
+                let mut by_name: HashMap<_, _> = levels.drain(..).map(|l| (l.name.clone(), l)).collect();
+                by_name.extend(added.iter().map(|l| (l.name.clone(), l.clone())));
+                for patch in changed {
+                    if let Some(level) = by_name.get_mut(&patch.name) {
+                        patch.apply(level);
+                    }
+                }
+                levels.extend(order.iter().filter_map(|name| by_name.remove(name)));
+            }
+        }
+    }
+}
+
+impl Map {
+    /// Creates a patch that turns `old` into `new`. See [`MapPatch`].
+    #[must_use]
+    pub fn create_patch(old: &Map, new: &Map) -> MapPatch {
+        let rest = |map: &Map| Map {
+            levels: Vec::new(),
+            package: map.package.clone(),
+            filler: map.filler.clone(),
+            foregrounds: map.foregrounds.clone(),
+            backgrounds: map.backgrounds.clone(),
+            bg_color: map.bg_color,
+            extra_data: map.extra_data.clone(),
+            extra_children: map.extra_children.clone(),
+            header: map.header.clone()
+        };
+        let (old_rest, new_rest) = (rest(old), rest(new));
+
+        MapPatch {
+            rest: (old_rest != new_rest).then_some(new_rest),
+            levels: LevelListPatch::create(&old.levels, &new.levels)
+        }
+    }
+
+    /// Applies a patch to this map.
+    ///
+    /// Applying a patch made by [`Map::create_patch`] to the old map it was made from results in the new map.
+    pub fn apply_patch(&mut self, patch: &MapPatch) {
+        if let Some(rest) = &patch.rest {
+            let levels = std::mem::take(&mut self.levels);
+            self.clone_from(rest);
+            self.levels = levels;
+        }
+        if let Some(levels) = &patch.levels {
+            levels.apply(&mut self.levels);
+        }
+    }
+}
+
+// Patches are stored in the same element format as maps, under their own header.
+// Everything that a map can hold is stored the same way it is in a map,
+// and the patch structure is laid out around it:
+//
+// <MapPatch>
+//   <rest header=...><Map .../></rest>
+//   <levels replace=true>...levels</levels> or
+//   <levels>
+//     <order><level name=.../>...</order>
+//     <added>...levels</added>
+//     <changed><level name=...>...changed fields</level>...</changed>
+//   </levels>
+// </MapPatch>
+
+/// Creates an element with no attributes.
+fn element(name: &str, children: Vec<Element>) -> Element {
+    Element { name: name.into(), attributes: HashMap::new(), children }
+}
+
+/// Takes a required attribute out of an element.
+fn take(el: &mut Element, key: &'static str) -> Result<Value, LoadError> {
+    el.attributes.remove(key).ok_or(LoadError::MissingElement(key))
+}
+
+/// Takes a required string attribute out of an element.
+fn take_string(el: &mut Element, key: &'static str) -> Result<String, LoadError> {
+    match take(el, key)? {
+        Value::String(string) | Value::RleString(string) => Ok(string),
+        other => Err(LoadError::InvalidFieldType(key, other))
+    }
+}
+
+/// Takes a required non-negative integer attribute out of an element.
+fn take_usize(el: &mut Element, key: &'static str) -> Result<usize, LoadError> {
+    match take(el, key)? {
+        Value::Integer(int) => usize::try_from(int).map_err(|_| LoadError::InvalidFieldData(key, int.to_string())),
+        other => Err(LoadError::InvalidFieldType(key, other))
+    }
+}
+
+/// Checks that an element has the expected name.
+fn expect_name(el: &Element, name: &'static str) -> Result<(), LoadError> {
+    if &*el.name == name {
+        Ok(())
+    } else {
+        Err(LoadError::InvalidElementName(el.name.to_string(), name))
+    }
+}
+
+/// Converts a list of elements into typed values.
+fn convert<T: TryFrom<Element, Error = LoadError>>(el: Element) -> Result<Vec<T>, LoadError> {
+    el.children.into_iter().map(T::try_from).collect()
+}
+
+impl<T: TilemapCell> TilemapPatch<T> {
+    fn to_element(&self, name: &str) -> io::Result<Element> {
+        let int = |value: usize| i32::try_from(value)
+            .map_err(|_| io::Error::other("tilemap is too large to store in a patch"));
+        let mut el = element(name, Vec::new());
+        el.attributes.extend([
+            ("width".into(), int(self.width)?.into()),
+            ("height".into(), int(self.height)?.into()),
+            ("empty".into(), T::write_cells(&[self.empty]).into())
+        ]);
+        for (start, cells) in &self.runs {
+            let mut run = element("run", Vec::new());
+            run.attributes.extend([
+                ("start".into(), int(*start)?.into()),
+                ("cells".into(), T::write_cells(cells).into())
+            ]);
+            el.children.push(run);
+        }
+        Ok(el)
+    }
+
+    fn from_element(mut el: Element, name: &'static str) -> Result<Self, LoadError> {
+        let width = take_usize(&mut el, "width")?;
+        let height = take_usize(&mut el, "height")?;
+        let empty = take_string(&mut el, "empty")?;
+        let Some(&[empty]) = T::read_cells(&empty).as_deref() else {
+            return Err(LoadError::InvalidFieldData(name, format!("invalid empty tile {empty:?}")));
+        };
+        let runs = el.children.into_iter().map(|mut run| {
+            expect_name(&run, "run")?;
+            let start = take_usize(&mut run, "start")?;
+            let cells = take_string(&mut run, "cells")?;
+            let cells = T::read_cells(&cells)
+                .ok_or_else(|| LoadError::InvalidFieldData(name, format!("invalid tiles {cells:?}")))?;
+            Ok((start, cells))
+        }).collect::<Result<_, LoadError>>()?;
+        Ok(Self { width, height, empty, runs })
+    }
+}
+
+impl EntityListPatch {
+    fn to_element(&self, name: &str) -> Element {
+        match self {
+            EntityListPatch::Replace(entities) => {
+                let mut el = element(name, entities.iter().cloned().map(Into::into).collect());
+                el.attributes.insert("replace".into(), true.into());
+                el
+            },
+            EntityListPatch::Edit { order, upserted } => {
+                let mut el = element(name, upserted.iter().cloned().map(Into::into).collect());
+                el.attributes.insert("order".into(), order.iter().join(",").into());
+                el
+            }
+        }
+    }
+
+    fn from_element(mut el: Element, kind: EntityKind) -> Result<Self, LoadError> {
+        let convert = |el: Element| el.children.into_iter()
+            .map(|child| Ok(Entity { kind, ..Entity::try_from(child)? }))
+            .collect::<Result<Vec<_>, LoadError>>();
+        if el.attributes.remove("replace").is_some() {
+            return Ok(EntityListPatch::Replace(convert(el)?));
+        }
+        let order = take_string(&mut el, "order")?;
+        let order = if order.is_empty() { Vec::new() } else {
+            order.split(',')
+                .map(|id| id.parse().map_err(|_| LoadError::InvalidFieldData("order", order.clone())))
+                .collect::<Result<_, _>>()?
+        };
+        Ok(EntityListPatch::Edit { order, upserted: convert(el)? })
+    }
+}
+
+impl LevelPatch {
+    fn to_element(&self) -> io::Result<Element> {
+        let mut el = element("level", Vec::new());
+        el.attributes.insert("name".into(), self.name.clone().into());
+        if let Some(data) = &self.data {
+            let mut data_el = element("data", Vec::new());
+            data.clone().store_to(&mut data_el);
+            el.children.push(data_el);
+        }
+        for (name, patch) in [("entities", &self.entities), ("triggers", &self.triggers)] {
+            el.children.extend(patch.as_ref().map(|patch| patch.to_element(name)));
+        }
+        for (name, decals) in [("bgdecals", &self.bg_decals), ("fgdecals", &self.fg_decals)] {
+            if let Some(decals) = decals {
+                el.children.push(element(name, decals.iter().cloned().map(Into::into).collect()));
+            }
+        }
+        for (name, patch) in [("bg", &self.bg), ("solids", &self.solids)] {
+            el.children.extend(patch.as_ref().map(|patch| patch.to_element(name)).transpose()?);
+        }
+        for (name, patch) in [("bgtiles", &self.bg_tiles), ("fgtiles", &self.fg_tiles), ("objtiles", &self.obj_tiles)] {
+            el.children.extend(patch.as_ref().map(|patch| patch.to_element(name)).transpose()?);
+        }
+        if let Some(extra_data) = &self.extra_data {
+            let mut data_el = element("extraData", Vec::new());
+            data_el.attributes = shared_keys(extra_data.clone());
+            el.children.push(data_el);
+        }
+        if let Some(extra_children) = &self.extra_children {
+            el.children.push(element("extraChildren", extra_children.clone()));
+        }
+        Ok(el)
+    }
+
+    fn from_element(mut el: Element) -> Result<Self, LoadError> {
+        expect_name(&el, "level")?;
+        let mut patch = LevelPatch { name: take_string(&mut el, "name")?, ..Default::default() };
+        for mut child in el.children {
+            match &*child.name {
+                "data" => patch.data = Some(LevelData::load_from(&mut child)?),
+                "entities" => patch.entities = Some(EntityListPatch::from_element(child, EntityKind::Entity)?),
+                "triggers" => patch.triggers = Some(EntityListPatch::from_element(child, EntityKind::Trigger)?),
+                "bgdecals" => patch.bg_decals = Some(convert(child)?),
+                "fgdecals" => patch.fg_decals = Some(convert(child)?),
+                "bg" => patch.bg = Some(TilemapPatch::from_element(child, "bg")?),
+                "solids" => patch.solids = Some(TilemapPatch::from_element(child, "solids")?),
+                "bgtiles" => patch.bg_tiles = Some(TilemapPatch::from_element(child, "bgtiles")?),
+                "fgtiles" => patch.fg_tiles = Some(TilemapPatch::from_element(child, "fgtiles")?),
+                "objtiles" => patch.obj_tiles = Some(TilemapPatch::from_element(child, "objtiles")?),
+                "extraData" => patch.extra_data = Some(owned_keys(child.attributes)),
+                "extraChildren" => patch.extra_children = Some(child.children),
+                _ => return Err(LoadError::InvalidElementName(child.name.to_string(), "level"))
+            }
+        }
+        Ok(patch)
+    }
+}
+
+impl LevelListPatch {
+    fn to_element(&self) -> io::Result<Element> {
+        Ok( match self {
+            LevelListPatch::Replace(levels) => {
+                let mut el = element("levels", levels.iter().cloned().map(Into::into).collect());
+                el.attributes.insert("replace".into(), true.into());
+                el
+            },
+            LevelListPatch::Edit { order, added, changed } => element("levels", vec![
+                element("order", order.iter().map(|name| {
+                    let mut level = element("level", Vec::new());
+                    level.attributes.insert("name".into(), name.clone().into());
+                    level
+                }).collect()),
+                element("added", added.iter().cloned().map(Into::into).collect()),
+                element("changed", changed.iter().map(LevelPatch::to_element).collect::<io::Result<_>>()?)
+            ])
+        } )
+    }
+
+    fn from_element(mut el: Element) -> Result<Self, LoadError> {
+        if el.attributes.remove("replace").is_some() {
+            return Ok(LevelListPatch::Replace(convert(el)?));
+        }
+        let [order, added, changed] = <[Element; 3]>::try_from(el.children)
+            .map_err(|_| LoadError::MissingElement("levels"))?;
+        expect_name(&order, "order")?;
+        expect_name(&added, "added")?;
+        expect_name(&changed, "changed")?;
+        Ok(LevelListPatch::Edit {
+            order: order.children.into_iter()
+                .map(|mut level| {
+                    expect_name(&level, "level")?;
+                    take_string(&mut level, "name")
+                })
+                .collect::<Result<_, _>>()?,
+            added: convert(added)?,
+            changed: changed.children.into_iter().map(LevelPatch::from_element).collect::<Result<_, _>>()?
+        })
+    }
+}
+
+impl MapPatch {
+    /// The header at the start of a stored patch, in place of the `CELESTE MAP` header of a map.
+    pub const HEADER: &'static str = "CELESTE MAP PATCH";
+
+    /// Writes this patch to a writable stream, in a compact binary form.
+    /// 
+    /// Patches are stored in the same format as maps (see [`Map::store`]), starting with [`MapPatch::HEADER`].
+    /// Strings that repeat throughout the patch are only stored once,
+    /// and changed tilemaps only store the runs of cells that changed.
+    /// 
+    /// # Errors
+    /// Errors if writing to the stream fails, or if the patch holds something that can't be stored,
+    /// like a NaN float.
+    pub fn store(&self, stream: &mut dyn io::Write) -> Result<(), StoreError> {
+        let mut children = Vec::new();
+        if let Some(rest) = &self.rest {
+            let mut rest = rest.clone();
+            let header = rest.header.take();
+            let mut rest_el = element("rest", vec![rest.into()]);
+            rest_el.attributes.extend(header.map(|header| ("header".into(), header.into())));
+            children.push(rest_el);
+        }
+        if let Some(levels) = &self.levels {
+            children.push(levels.to_element()?);
+        }
+        let mut el = element("MapPatch", children);
+        el.check_floats(false)?;
+
+        Ok(crate::store_element(el, "", stream, Some(Self::HEADER))?)
+    }
+
+    /// Reads a patch written by [`MapPatch::store`] from a readable stream.
+    /// 
+    /// # Errors
+    /// Errors if the stream doesn't start with [`MapPatch::HEADER`], or if the patch fails to load.
+    /// See [`LoadError`] for more information.
+    pub fn load(stream: &mut dyn io::Read) -> Result<MapPatch, LoadError> {
+        let header = stream.read_string(false)?;
+        if header != Self::HEADER {
+            return Err(LoadError::InvalidHeader(header));
+        }
+        let mut el = crate::load_element(stream, LoadOptions { check_header: false, ..Default::default() }, &mut |_| ())?;
+        expect_name(&el, "MapPatch")?;
+        el.attributes.remove("_package");
+
+        let mut patch = MapPatch::default();
+        for mut child in el.children {
+            match &*child.name {
+                "rest" => {
+                    let mut rest = Map::try_from(child.children.pop().ok_or(LoadError::MissingElement("Map"))?)?;
+                    rest.header = child.attributes.contains_key("header").then(|| take_string(&mut child, "header")).transpose()?;
+                    patch.rest = Some(rest);
+                },
+                "levels" => patch.levels = Some(LevelListPatch::from_element(child)?),
+                _ => return Err(LoadError::InvalidElementName(child.name.to_string(), "MapPatch"))
+            }
+        }
+        Ok(patch)
+    }
+}
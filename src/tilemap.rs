@@ -1,31 +1,62 @@
 #[cfg(target_pointer_width = "16")]
 compile_error!("tilemaps cannot properly function when usize is less than 32 bytes long");
 
-use std::{cmp::Ordering, iter, ops::{Index, IndexMut}};
+use std::{cmp::Ordering, collections::VecDeque, fmt::Write as _, iter, ops::{Index, IndexMut}};
 use itertools::Itertools;
 
 mod seal {
-    pub trait TilemapCell: Copy {
+    use itertools::Itertools as _;
+
+    pub trait TilemapCell: Copy + PartialEq {
         const EMPTY: Self;
         const SEPARATOR: &'static str;
+
+        /// Writes a list of cells as a string, the way they're stored in patches.
+        fn write_cells(cells: &[Self]) -> String;
+        /// Reads a list of cells written by [`TilemapCell::write_cells`].
+        fn read_cells(s: &str) -> Option<Vec<Self>>;
     }
     
     impl TilemapCell for i32 {
         const EMPTY: Self = -1;
         const SEPARATOR: &'static str = ", ";
+
+        fn write_cells(cells: &[Self]) -> String {
+            cells.iter().join(",")
+        }
+
+        fn read_cells(s: &str) -> Option<Vec<Self>> {
+            if s.is_empty() {
+                return Some(Vec::new());
+            }
+            s.split(',').map(|cell| cell.parse().ok()).collect()
+        }
     }
     
     impl TilemapCell for char {
         const EMPTY: Self = '0';
         const SEPARATOR: &'static str = "";
+
+        fn write_cells(cells: &[Self]) -> String {
+            cells.iter().collect()
+        }
+
+        fn read_cells(s: &str) -> Option<Vec<Self>> {
+            Some(s.chars().collect())
+        }
     }
 }
 
-use seal::TilemapCell;
+pub(crate) use seal::TilemapCell;
 
 
-#[derive(Clone, PartialEq, Eq, Default, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 /// A 2-dimensional tilemap for use in Celeste levels.
+///
+/// Each tilemap has a sentinel value that marks a cell as empty.
+/// This defaults to what Celeste uses (`'0'` for character tilemaps, and `-1` for integer tilemaps),
+/// but can be changed for maps that use those values meaningfully, including while loading with
+/// [`LoadOptions::empty_char_tile`](crate::LoadOptions::empty_char_tile) and [`LoadOptions::empty_int_tile`](crate::LoadOptions::empty_int_tile).
 
 // Safety contracts:
 // width * height <= usize::MAX
@@ -33,7 +64,19 @@ use seal::TilemapCell;
 pub struct Tilemap<T: TilemapCell> {
     width: usize,
     height: usize,
-    data: Vec<T>
+    data: Vec<T>,
+    empty: T
+}
+
+impl<T: TilemapCell> Default for Tilemap<T> {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            data: Vec::new(),
+            empty: T::EMPTY
+        }
+    }
 }
 
 impl<T: TilemapCell + std::fmt::Display> std::fmt::Debug for Tilemap<T> {
@@ -60,17 +103,35 @@ impl<T: TilemapCell> Tilemap<T> {
     /// 
     /// Will return [`None`] if the width and height cannot be multiplied as [`usize`]s without arithmetic overflow.
     pub fn new(width: usize, height: usize) -> Option<Self> {
+        Self::with_empty(width, height, T::EMPTY)
+    }
+
+    /// Creates a new tilemap of the given width and height, using a custom sentinel value for empty cells.
+    /// 
+    /// Will return [`None`] if the width and height cannot be multiplied as [`usize`]s without arithmetic overflow.
+    pub fn with_empty(width: usize, height: usize, empty: T) -> Option<Self> {
         (height).checked_mul(width)
             .map(|size| 
                 Self {
                     width, height,
-                    data: iter::repeat(T::EMPTY)
-                        .take(size)
-                        .collect()
+                    data: vec![empty; size],
+                    empty
                 }
             )
     }
 
+    /// Gets the sentinel value that marks a cell as empty.
+    pub fn empty(&self) -> T {
+        self.empty
+    }
+
+    /// Sets the sentinel value that marks a cell as empty.
+    /// 
+    /// This does not change any cells. Cells holding the old sentinel become real data.
+    pub fn set_empty(&mut self, empty: T) {
+        self.empty = empty;
+    }
+
     /// Gets the width of the tilemap.
     pub fn width(&self) -> usize {
         self.width
@@ -89,6 +150,8 @@ impl<T: TilemapCell> Tilemap<T> {
 
         match self.width.cmp(&new_width) {
             Ordering::Equal => (),
+            // There are no rows to pad out, only a height
+            Ordering::Less if self.width == 0 => self.data = vec![self.empty; new_width * self.height],
             Ordering::Less =>
                 // Pad out the width
                 self.data = self.data.iter()
@@ -96,7 +159,7 @@ impl<T: TilemapCell> Tilemap<T> {
                     .chunks(self.width)
                     .into_iter()
                     .flat_map(|chunk| chunk.chain(
-                        iter::repeat(T::EMPTY).take(new_width - self.width)
+                        iter::repeat(self.empty).take(new_width - self.width)
                     ))
                     .collect(),
             Ordering::Greater =>
@@ -119,14 +182,10 @@ impl<T: TilemapCell> Tilemap<T> {
 
         match self.height.cmp(&new_height) {
             Ordering::Equal => (),
-            Ordering::Less => self.data = self.data.iter()
-                .copied()
-                .chain(iter::repeat(T::EMPTY).take(
-                    self.width * new_height // cannot overflow due to above
-                ))
-                .collect(),
+            Ordering::Less => self.data.resize(new_area, self.empty), // cannot overflow due to above
             Ordering::Greater => self.data.truncate(new_area)
         }
+        self.height = new_height;
 
         true
     }
@@ -169,6 +228,54 @@ impl<T: TilemapCell> Tilemap<T> {
         self.data.get_unchecked_mut(self.width * y + x)
     }
 
+    /// Copies every cell of `source` into this tilemap, with its top-left corner at `(x, y)`.
+    /// 
+    /// Cells that would land outside of this tilemap are skipped.
+    pub fn blit(&mut self, source: &Self, x: usize, y: usize) {
+        for (source_y, row) in source.data.chunks_exact(source.width.max(1)).enumerate() {
+            let Some(dest_y) = y.checked_add(source_y) else { break };
+            for (source_x, cell) in row.iter().enumerate() {
+                let Some(dest_x) = x.checked_add(source_x) else { break };
+                if let Some(dest) = self.get_mut(dest_x, dest_y) {
+                    *dest = *cell;
+                }
+            }
+        }
+    }
+
+    /// Checks whether the `width` by `height` rectangle with its top-left corner at `(x, y)`
+    /// holds the same cells in both tilemaps.
+    /// 
+    /// Returns [`None`] if the rectangle doesn't fit inside of either tilemap.
+    #[must_use]
+    pub fn region_eq(&self, other: &Self, x: usize, y: usize, width: usize, height: usize) -> Option<bool> {
+        let right = x.checked_add(width)?;
+        let bottom = y.checked_add(height)?;
+        if right > self.width.min(other.width) || bottom > self.height.min(other.height) {
+            return None;
+        }
+        Some( (y .. bottom).all(|row|
+            self.data[row * self.width + x .. row * self.width + right]
+                == other.data[row * other.width + x .. row * other.width + right]
+        ) )
+    }
+
+    /// Applies a function to every cell of the tilemap in place.
+    pub fn map_cells(&mut self, mut f: impl FnMut(T) -> T) {
+        for cell in &mut self.data {
+            *cell = f(*cell);
+        }
+    }
+
+    /// Applies a function to every cell of the tilemap in place, spread across threads with [`rayon`].
+    /// 
+    /// This gives the same results as [`Tilemap::map_cells`], but `f` can't rely on the order cells are visited in.
+    #[cfg(feature = "rayon")]
+    pub fn par_map_cells(&mut self, f: impl Fn(T) -> T + Sync + Send) where T: Send {
+        use rayon::iter::{IntoParallelRefMutIterator as _, ParallelIterator as _};
+        self.data.par_iter_mut().for_each(|cell| *cell = f(*cell));
+    }
+
     /// Gets a reference to the underlying raw data of the tilemap.
     pub fn raw_data(&self) -> &[T] {
         &self.data
@@ -181,6 +288,95 @@ impl<T: TilemapCell> Tilemap<T> {
     pub unsafe fn raw_data_mut(&mut self) -> &mut [T] {
         &mut self.data
     }
+
+    /// Gets a reference to the cell at a signed index, returning [`None`] if out of bounds, including if negative.
+    /// 
+    /// This is useful for neighbor lookups, where an offset may go below zero.
+    pub fn get_signed(&self, x: isize, y: isize) -> Option<&T> {
+        self.get(usize::try_from(x).ok()?, usize::try_from(y).ok()?)
+    }
+
+    /// Gets a mutable reference to the cell at a signed index, returning [`None`] if out of bounds, including if negative.
+    pub fn get_signed_mut(&mut self, x: isize, y: isize) -> Option<&mut T> {
+        self.get_mut(usize::try_from(x).ok()?, usize::try_from(y).ok()?)
+    }
+
+    /// Inserts an empty row before row `at`, shifting every row below it down.
+    /// 
+    /// Returns `false` without changing anything if `at` is past the bottom of the tilemap,
+    /// or if the new area would be greater than [`usize::MAX`].
+    pub fn insert_row(&mut self, at: usize) -> bool {
+        if at > self.height { return false; }
+        let Some(new_height) = self.height.checked_add(1) else { return false };
+        let Some(_) = self.width.checked_mul(new_height) else { return false };
+
+        let start = at * self.width;
+        self.data.splice(start .. start, iter::repeat_n(self.empty, self.width));
+        self.height = new_height;
+        true
+    }
+
+    /// Removes row `at`, shifting every row below it up.
+    /// 
+    /// Returns `false` without changing anything if the row is out of bounds.
+    pub fn remove_row(&mut self, at: usize) -> bool {
+        if at >= self.height { return false; }
+
+        let start = at * self.width;
+        self.data.drain(start .. start + self.width);
+        self.height -= 1;
+        true
+    }
+
+    /// Inserts an empty column before column `at`, shifting every column to the right of it over.
+    /// 
+    /// Returns `false` without changing anything if `at` is past the right edge of the tilemap,
+    /// or if the new area would be greater than [`usize::MAX`].
+    pub fn insert_col(&mut self, at: usize) -> bool {
+        if at > self.width { return false; }
+        let Some(new_width) = self.width.checked_add(1) else { return false };
+        let Some(_) = self.height.checked_mul(new_width) else { return false };
+
+        let mut data = Vec::with_capacity(self.height * new_width);
+        for row in 0 .. self.height {
+            let start = row * self.width;
+            data.extend_from_slice(&self.data[start .. start + at]);
+            data.push(self.empty);
+            data.extend_from_slice(&self.data[start + at .. start + self.width]);
+        }
+        self.data = data;
+        self.width = new_width;
+        true
+    }
+
+    /// Removes column `at`, shifting every column to the right of it over.
+    /// 
+    /// Returns `false` without changing anything if the column is out of bounds.
+    pub fn remove_col(&mut self, at: usize) -> bool {
+        if at >= self.width { return false; }
+
+        let width = self.width;
+        let mut index = 0;
+        self.data.retain(|_| {
+            let keep = index % width != at;
+            index += 1;
+            keep
+        });
+        self.width -= 1;
+        true
+    }
+
+    /// Counts the cells that aren't empty.
+    #[must_use]
+    pub fn non_empty_count(&self) -> usize {
+        self.data.iter().filter(|&&cell| cell != self.empty).count()
+    }
+
+    /// Checks whether every cell is empty, including if the tilemap has no cells at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.iter().all(|&cell| cell == self.empty)
+    }
 }
 
 impl<T: TilemapCell> Index<(usize, usize)> for Tilemap<T> {
@@ -197,9 +393,146 @@ impl<T: TilemapCell> IndexMut<(usize, usize)> for Tilemap<T> {
     }
 }
 
+/// The changes to a single [`Tilemap`], as runs of cells that differ from the old tilemap.
+/// 
+/// Runs are indexed in the same row-by-row order as [`Tilemap::raw_data`], using the new tilemap's width.
+/// Cells that weren't in the old tilemap are compared against the new tilemap's empty sentinel.
+/// See [`MapPatch`](crate::MapPatch).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TilemapPatch<T: TilemapCell> {
+    /// The width of the new tilemap.
+    pub width: usize,
+    /// The height of the new tilemap.
+    pub height: usize,
+    /// The empty sentinel of the new tilemap.
+    pub empty: T,
+    /// The runs of changed cells, as the index of the first cell in the run and the new values of its cells.
+    pub runs: Vec<(usize, Vec<T>)>
+}
+
+impl<T: TilemapCell> TilemapPatch<T> {
+    /// Finds the runs of cells that changed between two tilemaps, returning [`None`] if nothing changed.
+    pub(crate) fn create(old: &Tilemap<T>, new: &Tilemap<T>) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+
+        let mut runs: Vec<(usize, Vec<T>)> = Vec::new();
+        for (i, &cell) in new.data.iter().enumerate() {
+            // There's at least one cell, so the width isn't zero
+            let (x, y) = (i % new.width, i / new.width);
+            if old.get(x, y).copied().unwrap_or(new.empty) == cell {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((start, cells)) if *start + cells.len() == i => cells.push(cell),
+                _ => runs.push((i, vec![cell]))
+            }
+        }
+
+        Some(Self { width: new.width, height: new.height, empty: new.empty, runs })
+    }
+
+    /// Applies this patch to a tilemap, resizing it and changing its empty sentinel to match.
+    /// 
+    /// Cells of runs that go past the end of the tilemap are ignored.
+    pub fn apply(&self, tilemap: &mut Tilemap<T>) {
+        let Some(mut patched) = Tilemap::with_empty(self.width, self.height, self.empty) else { return };
+        for y in 0 .. self.height.min(tilemap.height) {
+            for x in 0 .. self.width.min(tilemap.width) {
+                patched.data[y * self.width + x] = tilemap.data[y * tilemap.width + x];
+            }
+        }
+        for (start, cells) in &self.runs {
+            let dest = patched.data.iter_mut().skip(*start);
+            for (dest, &cell) in dest.zip(cells) {
+                *dest = cell;
+            }
+        }
+        *tilemap = patched;
+    }
+}
+
 impl Tilemap<char> {
-    pub(crate) fn load(s: String, width: usize, height: usize) -> Option<Self> {
-        let mut map = Self::new(width, height)?;
+    pub(crate) fn load(s: &str, width: usize, height: usize) -> Option<Self> {
+        Self::load_with_empty(s, width, height, char::EMPTY)
+    }
+
+    /// Finds tiles that don't touch any other tile on any of their four sides, as `(x, y)` pairs, row by row.
+    /// 
+    /// Celeste's autotiler can't always find a texture for a lone tile, so these are usually mistakes.
+    /// Tiles on the edge of the tilemap count as touching the edge, the same way Celeste treats them.
+    #[must_use]
+    pub fn isolated_tiles(&self) -> Vec<(usize, usize)> {
+        let filled = |x: usize, y: usize, dx: isize, dy: isize| {
+            let (Some(x), Some(y)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else { return true };
+            self.get(x, y).is_none_or(|&cell| cell != self.empty)
+        };
+
+        (0 .. self.height)
+            .flat_map(|y| (0 .. self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self[(x, y)] != self.empty)
+            .filter(|&(x, y)| [(0, -1), (0, 1), (-1, 0), (1, 0)].iter().all(|&(dx, dy)| !filled(x, y, dx, dy)))
+            .collect()
+    }
+
+    /// Gets which cells are solid, as a grid of booleans in the same row-by-row order as [`Tilemap::raw_data`].
+    /// 
+    /// Any cell that isn't empty counts as solid, no matter which tile it is.
+    #[must_use]
+    pub fn collision_grid(&self) -> Vec<bool> {
+        self.data.iter().map(|&cell| cell != self.empty).collect()
+    }
+
+    /// Checks whether there's a path of empty cells from `from` to `to`, moving up, down, left, or right.
+    /// 
+    /// Returns `false` if either cell is solid or out of bounds. See [`Tilemap::collision_grid`].
+    #[must_use]
+    pub fn reachable(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let index = |(x, y): (usize, usize)| (x < self.width && y < self.height).then(|| y * self.width + x);
+        let (Some(start), Some(end)) = (index(from), index(to)) else { return false };
+        // Solid cells are marked as seen, so they're never walked into
+        let mut seen = self.collision_grid();
+        if seen[start] || seen[end] {
+            return false;
+        }
+
+        let mut queue = VecDeque::from([start]);
+        seen[start] = true;
+        while let Some(cell) = queue.pop_front() {
+            if cell == end {
+                return true;
+            }
+            let (x, y) = (cell % self.width, cell / self.width);
+            let neighbors = [
+                (x > 0).then(|| cell - 1),
+                (x + 1 < self.width).then(|| cell + 1),
+                (y > 0).then(|| cell - self.width),
+                (y + 1 < self.height).then(|| cell + self.width)
+            ];
+            for neighbor in neighbors.into_iter().flatten() {
+                if !seen[neighbor] {
+                    seen[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        false
+    }
+
+    /// Iterates over the runs of identical tiles in each row, as `(tile, length)` pairs.
+    /// 
+    /// Runs never cross from one row into the next, and empty cells at the end of each row are left out,
+    /// the same way as in [`Tilemap::store`]. A row that's entirely empty has no runs at all.
+    pub fn runs(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        self.data.chunks_exact(self.width.max(1)).flat_map(|row| {
+            let end = row.iter().rposition(|&cell| cell != self.empty).map_or(0, |i| i + 1);
+            row[.. end].iter().copied().dedup_with_count().map(|(length, tile)| (tile, length))
+        })
+    }
+
+    pub(crate) fn load_with_empty(s: &str, width: usize, height: usize, empty: char) -> Option<Self> {
+        let mut map = Self::with_empty(width, height, empty)?;
         for (y, line) in s.lines().enumerate() {
             for (x, chr) in line.chars().enumerate() {
                 if let Some(addr) = map.get_mut(x, y) {
@@ -211,35 +544,49 @@ impl Tilemap<char> {
         Some(map)
     }
 
-    pub(crate) fn store(&self) -> String {
-        let mut rows = Vec::with_capacity(self.height);
-        for row in self.data.chunks_exact(self.width) {
-            let mut buf = String::with_capacity(self.width);
+    /// Gets this tilemap in the text format Celeste stores it in. See [`Tilemap::store_into`].
+    #[must_use]
+    pub fn store(&self) -> String {
+        let mut buf = String::with_capacity(self.data.len() + self.height);
+        self.store_into(&mut buf);
+        buf
+    }
+
+    /// Writes this tilemap in the text format Celeste stores it in, appending it to `buf`.
+    /// 
+    /// Rows are separated by newlines, and empty cells at the end of each row are left out.
+    /// Reusing the same buffer across tilemaps avoids allocating a new string for each one.
+    pub fn store_into(&self, buf: &mut String) {
+        for (y, row) in self.data.chunks_exact(self.width.max(1)).enumerate() {
+            if y > 0 {
+                buf.push('\n');
+            }
             let mut last_run = 0;
             for char in row.iter().copied() {
-                if char == char::EMPTY {
+                if char == self.empty {
                     last_run += 1;
                     continue;
-                } else if last_run > 0 {
-                    buf.extend(iter::repeat(char::EMPTY).take(last_run));
-                    last_run = 0;
                 }
+                buf.extend(iter::repeat(self.empty).take(last_run));
+                last_run = 0;
                 buf.push(char);
             }
-            rows.push(buf);
         }
-        rows.join("\n").into()
     }
 }
 
 impl Tilemap<i32> {
-    pub(crate) fn load(s: String, width: usize, height: usize) -> Option<Self> {
-        let mut map = Self::new(width, height)?;
+    pub(crate) fn load(s: &str, width: usize, height: usize) -> Option<Self> {
+        Self::load_with_empty(s, width, height, i32::EMPTY)
+    }
+
+    pub(crate) fn load_with_empty(s: &str, width: usize, height: usize, empty: i32) -> Option<Self> {
+        let mut map = Self::with_empty(width, height, empty)?;
         for (y, line) in s.lines().enumerate() {
             for (x, id) in line.split(',').map(|v| v.trim().parse()).enumerate() {
                 if let Some(addr) = map.get_mut(x, y) {
                     // This needs to be pretty damn resilient. Customs can be quite broken sometimes.
-                    *addr = id.unwrap_or(-1);
+                    *addr = id.unwrap_or(empty);
                 }
             }
         }
@@ -247,30 +594,89 @@ impl Tilemap<i32> {
         Some(map)
     }
 
-    pub(crate) fn store(&self) -> String {
-        let mut rows = Vec::with_capacity(self.height);
-        for row in self.data.chunks_exact(self.width) {
-            let mut line_buf = Vec::with_capacity(self.width);
+    /// Gets this tilemap in the text format Celeste stores it in. See [`Tilemap::store_into`].
+    #[must_use]
+    pub fn store(&self) -> String {
+        let mut buf = String::new();
+        self.store_into(&mut buf);
+        buf
+    }
+
+    /// Writes this tilemap in the text format Celeste stores it in, appending it to `buf`.
+    /// 
+    /// Rows are separated by newlines and cells by commas, and empty cells at the end of each row are left out.
+    /// Reusing the same buffer across tilemaps avoids allocating a new string for each one.
+    pub fn store_into(&self, buf: &mut String) {
+        for (y, row) in self.data.chunks_exact(self.width.max(1)).enumerate() {
+            if y > 0 {
+                buf.push('\n');
+            }
             let mut last_run = 0;
+            let mut first = true;
             for id in row.iter().copied() {
-                if id == i32::EMPTY {
+                if id == self.empty {
                     last_run += 1;
                     continue;
-                } else if last_run > 0 {
-                    line_buf.extend(
-                        iter::repeat(i32::EMPTY).take(last_run)
-                    );
-                    last_run = 0;
                 }
-                line_buf.push(id);
+                for id in iter::repeat(self.empty).take(last_run).chain([id]) {
+                    if !first {
+                        buf.push(',');
+                    }
+                    first = false;
+                    // Writing to a string can't fail
+                    let _ = write!(buf, "{id}");
+                }
+                last_run = 0;
             }
-            rows.push(
-                line_buf.into_iter()
-                    .map(|v| v.to_string())
-                    .join(",")
-            )
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tilemap;
+
+    #[test]
+    fn custom_empty_preserves_default_sentinel() {
+        let mut map = Tilemap::<i32>::with_empty(3, 2, 0).unwrap();
+        map[(0, 0)] = -1;
+        map[(1, 0)] = 5;
+        map[(2, 1)] = -1;
 
-        rows.into_iter().join("\n")
+        let stored = map.store();
+        assert_eq!(stored, "-1,5\n0,0,-1");
+
+        let loaded = Tilemap::<i32>::load_with_empty(&stored, 3, 2, 0).unwrap();
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.empty(), 0);
+    }
+
+    #[test]
+    fn empty_text_loads_as_empty_tilemaps() {
+        assert_eq!(Tilemap::<char>::load("", 3, 2), Tilemap::new(3, 2));
+        assert_eq!(Tilemap::<i32>::load("", 3, 2), Tilemap::new(3, 2));
+        assert_eq!(Tilemap::<i32>::load("\n,\n", 2, 2), Tilemap::new(2, 2));
+        assert_eq!(Tilemap::<char>::new(3, 2).unwrap().store(), "\n");
+        assert_eq!(Tilemap::<i32>::new(0, 0).unwrap().store(), "");
+    }
+
+    #[test]
+    fn store_into_matches_store() {
+        let mut chars = Tilemap::<char>::new(4, 3).unwrap();
+        chars[(1, 0)] = '3';
+        chars[(3, 2)] = 'a';
+        let mut ints = Tilemap::<i32>::new(4, 3).unwrap();
+        ints[(0, 0)] = 12;
+        ints[(2, 1)] = 7;
+
+        let mut buf = String::from("prefix|");
+        chars.store_into(&mut buf);
+        assert_eq!(buf, format!("prefix|{}", chars.store()));
+        assert_eq!(chars.store(), "03\n\n000a");
+
+        buf.clear();
+        ints.store_into(&mut buf);
+        assert_eq!(buf, ints.store());
+        assert_eq!(buf, "12\n-1,-1,7\n");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use strawberride::{Decal, Entity, EntityKind, Filler, Level, Map};
+
+/// Builds an empty level with the given pixel position and size.
+pub fn level(name: &str, position: (i32, i32), size: (i32, i32)) -> Level {
+    let mut level = Level::new(name, size.0, size.1).unwrap();
+    level.data.position = position;
+    level.data.wind_pattern = "None".into();
+    level
+}
+
+/// Builds an entity with the given name, id and position.
+pub fn entity(name: &str, id: i32, position: (f32, f32)) -> Entity {
+    Entity { name: name.into(), id, position, ..Default::default() }
+}
+
+/// Builds a map holding only the given levels.
+pub fn map(levels: Vec<Level>) -> Map {
+    Map { levels, ..Default::default() }
+}
+
+/// Builds a small map with a couple of populated levels.
+pub fn sample_map() -> Map {
+    let mut first = level("a-00", (0, 0), (320, 184));
+    for x in 0..40 {
+        first.solids[(x, 22)] = '1';
+    }
+    first.bg[(3, 3)] = '2';
+    first.fg_tiles[(1, 1)] = 12;
+    first.entities.push(Entity {
+        name: "player".into(),
+        id: 1,
+        position: (16., 168.),
+        ..Default::default()
+    });
+    first.entities.push(Entity {
+        name: "spikesUp".into(),
+        id: 2,
+        position: (64., 168.),
+        width: Some(16),
+        ..Default::default()
+    });
+    first.triggers.push(Entity {
+        name: "windTrigger".into(),
+        id: 3,
+        position: (0., 0.),
+        width: Some(32),
+        height: Some(32),
+        kind: EntityKind::Trigger,
+        ..Default::default()
+    });
+    first.fg_decals.push(Decal {
+        position: (40., 40.),
+        scale: (1., 1.),
+        texture: "decals/1-forsakencity/flag.png".into(),
+        color: [0xFF; 4],
+        ..Default::default()
+    });
+
+    let mut second = level("a-01", (320, 0), (320, 184));
+    second.solids[(0, 0)] = '3';
+    second.bg_decals.push(Decal {
+        position: (8., 8.),
+        scale: (1., 1.),
+        texture: "decals/generic/grass_a.png".into(),
+        color: [0xFF; 4],
+        depth: 2,
+        ..Default::default()
+    });
+
+    Map {
+        package: "sample".into(),
+        filler: vec![Filler { position: (80, 0), size: (4, 4) }],
+        levels: vec![first, second],
+        bg_color: Some([0x10, 0x20, 0x30, 0xFF]),
+        ..Default::default()
+    }
+}
+
+/// Stores a map into a byte buffer.
+pub fn store_bytes(map: Map) -> Vec<u8> {
+    let mut buf = Vec::new();
+    map.store(&mut buf, true).unwrap();
+    buf
+}
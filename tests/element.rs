@@ -0,0 +1,247 @@
+mod common;
+
+use std::{collections::HashMap, sync::Arc};
+
+use strawberride::{Element, Value};
+
+#[test]
+fn inner_text() {
+    let with_text = Element {
+        name: "solids".into(),
+        attributes: HashMap::from([("innerText".into(), Value::RleString("0110".into()))]),
+        children: vec![]
+    };
+    let without_text = Element {
+        name: "entities".into(),
+        attributes: HashMap::new(),
+        children: vec![]
+    };
+
+    assert_eq!(with_text.inner_text(), Some("0110"));
+    assert_eq!(without_text.inner_text(), None);
+}
+
+#[test]
+fn encoded_sizes() {
+    assert_eq!(Value::Boolean(true).encoded_size(), 2);
+    assert_eq!(Value::Integer(200).encoded_size(), 2);
+    assert_eq!(Value::Integer(-200).encoded_size(), 3);
+    assert_eq!(Value::Integer(40000).encoded_size(), 5);
+    assert_eq!(Value::String("short".into()).encoded_size(), 3);
+    assert_eq!(Value::String("x".repeat(200)).encoded_size(), 1 + 2 + 200);
+    assert_eq!(Value::RleString("aaab".into()).encoded_size(), 1 + 2 + 4);
+}
+
+#[test]
+fn color_values() {
+    use strawberride::Decal;
+
+    assert_eq!(Value::from_color([0xFF, 0, 0, 0xFF]), Value::String("ff0000ff".into()));
+    assert_eq!(Value::from([0x12, 0xab, 0x00, 0x80]), Value::String("12ab0080".into()));
+
+    let decal = Decal { color: [0x12, 0xab, 0x00, 0x80], ..Default::default() };
+    let el = Element::from(decal.clone());
+    assert_eq!(el.attributes["color"], Value::String("12ab0080".into()));
+    assert_eq!(Decal::try_from(el).unwrap(), decal);
+}
+
+#[test]
+fn element_to_map_bytes() {
+    use strawberride::Map;
+
+    let empty = |name: &str| Element {
+        name: name.into(),
+        attributes: HashMap::new(),
+        children: vec![]
+    };
+    let root = Element {
+        name: "Map".into(),
+        attributes: HashMap::new(),
+        children: vec![empty("Filler"), empty("Style"), empty("levels")]
+    };
+
+    let bytes = root.to_map_bytes("handmade", true).unwrap();
+    let map = Map::load(&mut bytes.as_slice(), true).unwrap();
+    assert_eq!(map.package, "handmade");
+    assert!(map.levels.is_empty());
+    assert!(map.extra_children.is_empty());
+}
+
+#[test]
+fn element_from_map_bytes() {
+    let bytes = common::store_bytes(common::sample_map());
+    let root = Element::from_map_bytes(&bytes, true).unwrap();
+    assert_eq!(&*root.name, "Map");
+    assert_eq!(root.attributes["_package"], Value::String("sample".into()));
+    assert!(root.children.iter().any(|child| &*child.name == "levels"));
+
+    assert!(Element::from_map_bytes(&bytes[1..], true).is_err());
+}
+
+#[test]
+fn wire_types() {
+    assert_eq!(Value::Boolean(false).wire_type(), 0);
+    assert_eq!(Value::Integer(200).wire_type(), 1);
+    assert_eq!(Value::Integer(-5).wire_type(), 2);
+    assert_eq!(Value::Integer(40000).wire_type(), 3);
+    assert_eq!(Value::Float(1.0).wire_type(), 4);
+    assert_eq!(Value::String("short".into()).wire_type(), 5);
+    assert_eq!(Value::String("x".repeat(100)).wire_type(), 6);
+    assert_eq!(Value::RleString("x".repeat(100)).wire_type(), 7);
+}
+
+#[test]
+fn inner_text_as_tilemaps() {
+    let tiles = |name: &str, text: Value| Element {
+        name: name.into(),
+        attributes: HashMap::from([("innerText".into(), text)]),
+        children: vec![]
+    };
+
+    let ints = tiles("fgtiles2", Value::String("1,2\n-1,-1,7".into())).as_tilemap_i32(3, 2).unwrap();
+    assert_eq!(ints.raw_data(), &[1, 2, -1, -1, -1, 7]);
+    let chars = tiles("solids2", Value::RleString("0a\nbcd".into())).as_tilemap_char(2, 3).unwrap();
+    assert_eq!(chars.raw_data(), &['0', 'a', 'b', 'c', '0', '0']);
+
+    let no_text = Element { name: "empty".into(), attributes: HashMap::new(), children: vec![] };
+    assert!(no_text.as_tilemap_char(2, 2).is_none());
+}
+
+#[test]
+fn encoding_is_deterministic() {
+    let keys: Vec<String> = (0 .. 40).map(|i| format!("attr{i}")).collect();
+    let element = |keys: &mut dyn Iterator<Item = &String>| Element {
+        name: "Map".into(),
+        attributes: keys.map(|key| (key.as_str().into(), Value::String(key.repeat(2)))).collect(),
+        children: vec![]
+    };
+    let forwards = element(&mut keys.iter());
+    let backwards = element(&mut keys.iter().rev());
+
+    let bytes = forwards.clone().to_map_bytes("det", true).unwrap();
+    assert_eq!(forwards.clone().to_map_bytes("det", true).unwrap(), bytes);
+    assert_eq!(backwards.to_map_bytes("det", true).unwrap(), bytes);
+
+    let names: Vec<_> = forwards.sorted_attributes().map(|(name, _)| &**name).collect();
+    assert!(names.is_sorted());
+}
+
+#[test]
+fn empty_strings_round_trip() {
+    let leaf = |name: &str, attributes: HashMap<Arc<str>, Value>| Element {
+        name: name.into(),
+        attributes,
+        children: vec![]
+    };
+    let root = leaf("Map", HashMap::from([
+        ("empty".into(), Value::String(String::new())),
+        ("emptyRle".into(), Value::RleString(String::new())),
+        // Long enough to be written inline instead of through the lookup table
+        ("long".into(), Value::String("x".repeat(100)))
+    ]));
+    let root = Element {
+        children: vec![leaf("solids", HashMap::from([("innerText".into(), Value::RleString(String::new()))]))],
+        ..root
+    };
+
+    let bytes = root.clone().to_map_bytes("", true).unwrap();
+    let mut loaded = Element::from_map_bytes(&bytes, true).unwrap();
+    assert_eq!(loaded.attributes.remove("_package"), Some(Value::String(String::new())));
+    assert_eq!(loaded, root);
+    assert_eq!(Value::String(String::new()).encoded_size(), 3);
+    assert_eq!(Value::RleString(String::new()).encoded_size(), 3);
+}
+
+#[test]
+fn empty_tilemaps_round_trip() {
+    use strawberride::Map;
+
+    // Every tilemap in the first level is empty, so they're all stored as blank rows
+    let map = common::map(vec![
+        common::level("empty", (0, 0), (32, 16)),
+        common::level("zero", (32, 0), (0, 0))
+    ]);
+
+    let loaded = Map::load(&mut &common::store_bytes(map.clone())[..], true).unwrap();
+    assert_eq!(loaded, map);
+    assert_eq!(loaded.levels[1].solids.raw_data(), &[]);
+}
+
+#[test]
+fn floats_stored_as_strings() {
+    use strawberride::Decal;
+
+    let mut el = Element::from(Decal { texture: "decals/generic/grass_a".into(), ..Default::default() });
+    el.attributes.insert("scaleX".into(), Value::String("2.0".into()));
+    el.attributes.insert("scaleY".into(), Value::RleString(" -1.5 ".into()));
+    el.attributes.insert("rotation".into(), Value::String("sideways".into()));
+
+    let decal = Decal::try_from(el.clone()).unwrap();
+    assert_eq!(decal.scale, (2.0, -1.5));
+    // Anything that doesn't parse falls back to the default
+    assert_eq!(decal.rotation, 0.0);
+
+    // So do floats that parse, but can't be stored
+    for text in ["NaN", "inf", "-infinity"] {
+        el.attributes.insert("rotation".into(), Value::String(text.into()));
+        assert_eq!(Decal::try_from(el.clone()).unwrap().rotation, 0.0, "{text}");
+    }
+}
+
+#[test]
+fn element_encoded_size() {
+    let map = common::sample_map();
+    let table = map.compute_string_table();
+    let bytes = common::store_bytes(map);
+    let mut root = Element::from_map_bytes(&bytes, true).unwrap();
+    root.attributes.remove("_package");
+
+    // The header, package name, and lookup table come first, and the root element is everything after that
+    let preamble = "\x0bCELESTE MAP\x06sample".len() + 2
+        + table.iter().map(|string| 1 + string.len()).sum::<usize>();
+    assert_eq!(preamble + root.encoded_size(), bytes.len());
+}
+
+#[test]
+fn color_string_lengths() {
+    use strawberride::{Decal, LoadError};
+
+    let decal = |color: &str| {
+        let mut el = Element::from(Decal::default());
+        el.attributes.insert("color".into(), Value::String(color.into()));
+        Decal::try_from(el)
+    };
+    assert_eq!(decal("0a1B2c3D").unwrap().color, [0x0a, 0x1b, 0x2c, 0x3d]);
+    assert_eq!(decal("A0B0C0").unwrap().color, [0xa0, 0xb0, 0xc0, 0xff]);
+
+    for invalid in ["0a1b2c3d4e", "0a1b2", "", "0a1b2g", "ééé"] {
+        assert!(
+            matches!(decal(invalid), Err(LoadError::InvalidFieldData("color", ref data)) if data == invalid),
+            "{invalid:?} was accepted"
+        );
+    }
+}
+
+#[test]
+fn walk_paths() {
+    let el = |name: &str, children| Element { name: name.into(), attributes: HashMap::new(), children };
+    let root = el("Map", vec![
+        el("levels", vec![
+            el("level", vec![el("entities", vec![el("player", vec![])])]),
+            el("level", vec![])
+        ]),
+        el("Filler", vec![])
+    ]);
+
+    let mut visited = Vec::new();
+    root.walk(|path, el| visited.push(format!("{}>{}", path.join("/"), el.name)));
+    assert_eq!(visited, [
+        ">Map",
+        "Map>levels",
+        "Map/levels>level",
+        "Map/levels/level>entities",
+        "Map/levels/level/entities>player",
+        "Map/levels>level",
+        "Map>Filler"
+    ]);
+}
@@ -0,0 +1,158 @@
+#!/usr/bin/env python3
+"""Writes reference.bin, the map that tests/format.rs checks storing and loading against.
+
+This encodes the format straight from its description, without going through the crate,
+so that the test catches changes to how the crate reads and writes maps.
+Run it from this directory after changing the reference map in both places.
+"""
+
+import struct
+
+# (name, {attribute: (kind, value)}, [children]).
+# Children are in the order the crate stores them, and attributes are written sorted by name.
+LEVEL_ATTRIBUTES = {
+    "name": ("str", "a-00"),
+    "x": ("int", 0), "y": ("int", 0), "width": ("int", 16), "height": ("int", 16),
+    "c": ("int", 0), "cameraOffsetX": ("int", 0), "cameraOffsetY": ("int", 0),
+    "music": ("str", ""), "alt_music": ("str", ""), "ambience": ("str", ""),
+    "musicProgress": ("str", ""), "ambienceProgress": ("str", ""), "windPattern": ("str", ""),
+    "musicLayer1": ("bool", False), "musicLayer2": ("bool", False),
+    "musicLayer3": ("bool", False), "musicLayer4": ("bool", False),
+    "dark": ("bool", False), "space": ("bool", False), "underwater": ("bool", False),
+    "whisper": ("bool", False), "disableDownTransition": ("bool", False), "delayAltMusicFade": ("bool", False),
+}
+
+def entity(name, attributes, nodes=()):
+    attributes = {"originX": ("float", 0.0), "originY": ("float", 0.0), **attributes}
+    return (name, attributes, [("node", {"x": ("float", x), "y": ("float", y)}, []) for x, y in nodes])
+
+def decal(attributes):
+    # Opaque white is the default color, so it isn't written.
+    return ("decal", attributes, [])
+
+def tiles(name, kind, text):
+    return (name, {"innerText": (kind, text)}, [])
+
+ROOT = ("Map", {}, [
+    ("meta", {"n": ("int", 300)}, []),
+    ("note", {"innerText": ("str", "0123456789" * 7)}, []),
+    ("Filler", {}, []),
+    ("Style", {"color": ("str", "102030ff")}, [
+        ("Foregrounds", {}, []),
+        ("Backgrounds", {}, []),
+    ]),
+    ("levels", {}, [
+        ("level", LEVEL_ATTRIBUTES, [
+            ("entities", {}, [
+                entity("spikesUp", {
+                    "id": ("int", 1), "x": ("float", 8.0), "y": ("float", 8.0),
+                    "width": ("int", 8), "type": ("str", "default"),
+                }, nodes=[(0.0, 8.0)]),
+            ]),
+            ("triggers", {}, [
+                entity("windTrigger", {
+                    "id": ("int", 2), "x": ("float", 0.0), "y": ("float", 0.0),
+                    "width": ("int", 16), "height": ("int", 8),
+                }),
+            ]),
+            ("bgdecals", {}, [
+                decal({
+                    "texture": ("str", "decals/1-forsakencity/plant_a"),
+                    "x": ("float", 4.0), "y": ("float", 12.0), "scaleX": ("float", 1.0), "scaleY": ("float", 1.0),
+                    "color": ("str", "ffffff80"),
+                }),
+            ]),
+            ("fgdecals", {}, [
+                decal({
+                    "texture": ("str", "decals/generic/snow_b"),
+                    "x": ("float", 12.0), "y": ("float", 4.0), "scaleX": ("float", -1.0), "scaleY": ("float", 1.0),
+                    "rotation": ("float", 90.0),
+                }),
+            ]),
+            tiles("bg", "rle", "\n02"),
+            tiles("bgtiles", "str", "\n"),
+            tiles("fgtiles", "str", "\n5"),
+            tiles("objtiles", "str", "\n"),
+            tiles("solids", "rle", "1\n"),
+        ]),
+    ]),
+])
+
+# Strings at least this long are written inline instead of going in the lookup table.
+LOOKUP_CUTOFF = 64
+
+
+def varint(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        out.append(byte | (0x80 if value else 0))
+        if not value:
+            return bytes(out)
+
+
+def string(value):
+    data = value.encode()
+    return varint(len(data)) + data
+
+
+def rle(value):
+    data = value.encode()
+    out = bytearray()
+    i = 0
+    while i < len(data):
+        run = 1
+        while i + run < len(data) and data[i + run] == data[i] and run < 255:
+            run += 1
+        out += bytes([run, data[i]])
+        i += run
+    return struct.pack("<H", len(out)) + out
+
+
+def encode(element, lookup):
+    def index(value):
+        if value not in lookup:
+            lookup.append(value)
+        return struct.pack("<H", lookup.index(value))
+
+    name, attributes, children = element
+    out = bytearray(index(name))
+    out.append(len(attributes))
+    for key in sorted(attributes):
+        kind, value = attributes[key]
+        out += index(key)
+        if kind == "bool":
+            out += bytes([0, value])
+        elif kind == "int" and 0 <= value <= 0xFF:
+            out += bytes([1, value])
+        elif kind == "int" and -0x8000 <= value <= 0x7FFF:
+            out += b"\x02" + struct.pack("<h", value)
+        elif kind == "int":
+            out += b"\x03" + struct.pack("<i", value)
+        elif kind == "float":
+            out += b"\x04" + struct.pack("<f", value)
+        elif kind == "str" and len(value.encode()) < LOOKUP_CUTOFF:
+            out += b"\x05" + index(value)
+        elif kind == "str":
+            out += b"\x06" + string(value)
+        elif kind == "rle":
+            out += b"\x07" + rle(value)
+    out += struct.pack("<H", len(children))
+    for child in children:
+        out += encode(child, lookup)
+    return bytes(out)
+
+
+def main():
+    lookup = []
+    body = encode(ROOT, lookup)
+    out = string("CELESTE MAP") + string("reference") + struct.pack("<H", len(lookup))
+    for value in lookup:
+        out += string(value)
+    with open("reference.bin", "wb") as file:
+        file.write(out + body)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use strawberride::{Decal, Element, Entity, EntityKind, Level, Map, Value};
+
+// Hand-assembled from the format description by fixtures/reference.py, not produced by this crate.
+// Attributes are stored sorted by name, so the output doesn't depend on attribute ordering.
+static REFERENCE: &[u8] = include_bytes!("fixtures/reference.bin");
+
+fn reference_map() -> Map {
+    let mut level = Level::new("a-00", 16, 16).unwrap();
+    level.solids[(0, 0)] = '1';
+    level.bg[(1, 1)] = '2';
+    level.fg_tiles[(0, 1)] = 5;
+    level.entities.push(Entity {
+        name: "spikesUp".into(),
+        id: 1,
+        position: (8.0, 8.0),
+        width: Some(8),
+        nodes: vec![(0.0, 8.0)],
+        values: HashMap::from([("type".into(), Value::String("default".into()))]),
+        ..Default::default()
+    });
+    level.triggers.push(Entity {
+        name: "windTrigger".into(),
+        id: 2,
+        width: Some(16),
+        height: Some(8),
+        kind: EntityKind::Trigger,
+        ..Default::default()
+    });
+    level.bg_decals.push(Decal {
+        texture: "decals/1-forsakencity/plant_a".into(),
+        position: (4.0, 12.0),
+        scale: (1.0, 1.0),
+        color: [0xFF, 0xFF, 0xFF, 0x80],
+        ..Default::default()
+    });
+    level.fg_decals.push(Decal {
+        texture: "decals/generic/snow_b".into(),
+        position: (12.0, 4.0),
+        scale: (-1.0, 1.0),
+        color: [0xFF; 4],
+        rotation: 90.0,
+        ..Default::default()
+    });
+
+    Map {
+        package: "reference".into(),
+        levels: vec![level],
+        bg_color: Some([0x10, 0x20, 0x30, 0xFF]),
+        extra_children: vec![
+            Element {
+                name: "meta".into(),
+                attributes: HashMap::from([("n".into(), Value::Integer(300))]),
+                children: vec![]
+            },
+            Element {
+                name: "note".into(),
+                attributes: HashMap::from([("innerText".into(), Value::String("0123456789".repeat(7)))]),
+                children: vec![]
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn store_matches_reference_bytes() {
+    let mut buf = Vec::new();
+    reference_map().store(&mut buf, true).unwrap();
+    assert_eq!(buf, REFERENCE);
+}
+
+#[test]
+fn reference_bytes_load() {
+    let map = Map::load(&mut &REFERENCE[..], true).unwrap();
+    assert_eq!(map, reference_map());
+}
@@ -0,0 +1,1114 @@
+mod common;
+
+#[cfg(feature = "memmap")]
+#[test]
+fn mmap_load_matches_stream_load() -> Result<(), Box<dyn std::error::Error>> {
+    use strawberride::Map;
+
+    let bytes = common::store_bytes(common::sample_map());
+    let path = std::env::temp_dir().join(format!("strawberride-mmap-{}.bin", std::process::id()));
+    std::fs::write(&path, &bytes)?;
+
+    // SAFETY: Nothing else knows about this file, so it can't change while it's mapped.
+    let mapped = unsafe { Map::load_mmap(&path) };
+    std::fs::remove_file(&path)?;
+    let loaded = Map::load(&mut bytes.as_slice(), true)?;
+    assert_eq!(mapped?, loaded);
+
+    Ok(())
+}
+
+#[test]
+fn semantic_eq_ignores_ordering() {
+    use std::collections::HashMap;
+    use strawberride::{Element, Value};
+
+    let extra = |name: &str| Element {
+        name: name.into(),
+        attributes: HashMap::new(),
+        children: vec![]
+    };
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    let mut a = common::map(vec![level]);
+    let mut b = a.clone();
+
+    a.extra_data.insert("first".into(), Value::Integer(1));
+    a.extra_data.insert("second".into(), Value::String("text".into()));
+    b.extra_data.insert("second".into(), Value::RleString("text".into()));
+    b.extra_data.insert("first".into(), Value::Integer(1));
+
+    a.extra_children = vec![extra("meta"), extra("notes")];
+    b.extra_children = vec![extra("notes"), extra("meta")];
+
+    assert_ne!(a, b);
+    assert!(a.semantic_eq(&b));
+
+    b.levels[0].entities[0].id = 100;
+    assert!(!a.semantic_eq(&b));
+}
+
+#[test]
+fn remove_entities_and_decals() {
+    use strawberride::Decal;
+
+    let marker = common::entity("debugMarker", 0, (0., 0.));
+    let spikes = common::entity("spikesUp", 1, (64., 168.));
+    let debug = Decal { texture: "decals/debug.png".into(), ..Default::default() };
+    let flag = Decal { texture: "decals/1-forsakencity/flag.png".into(), ..Default::default() };
+
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities = vec![spikes.clone(), marker.clone()];
+    first.bg_decals = vec![debug.clone(), flag.clone()];
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.entities = vec![marker.clone(), marker];
+    second.fg_decals = vec![debug];
+    let mut map = common::map(vec![first, second]);
+
+    assert_eq!(map.remove_entities_named("debugMarker"), 3);
+    assert_eq!(map.remove_entities_named("debugMarker"), 0);
+    assert_eq!(map.levels[0].entities, [spikes]);
+    assert!(map.levels[1].entities.is_empty());
+
+    assert_eq!(map.remove_decals_with_texture("decals/debug.png"), 2);
+    assert_eq!(map.levels[0].bg_decals, [flag]);
+    assert!(map.levels[1].fg_decals.is_empty());
+}
+
+#[test]
+fn load_levels_only_skips_style() -> Result<(), Box<dyn std::error::Error>> {
+    use std::collections::HashMap;
+    use strawberride::{Element, Map, Value};
+
+    let mut map = common::map(vec![common::level("a-00", (0, 0), (320, 184))]);
+    map.backgrounds.push(Element {
+        name: "parallax".into(),
+        attributes: HashMap::from([
+            ("texture".into(), Value::String("bgs/07/07/bg0".into())),
+            ("scrollx".into(), Value::Float(0.5)),
+        ]),
+        children: vec![]
+    });
+    let bytes = common::store_bytes(map.clone());
+
+    let levels = Map::load_levels_only(&mut bytes.as_slice(), true)?;
+    assert_eq!(levels, map.levels);
+    assert_eq!(Map::load(&mut bytes.as_slice(), true)?.levels, levels);
+
+    Ok(())
+}
+
+#[test]
+fn merge_side_by_side_levels() {
+    use strawberride::{Decal, EntityKind};
+
+    let mut left = common::level("a-00", (0, 0), (16, 16));
+    left.solids[(0, 1)] = '1';
+    left.fg_tiles[(1, 0)] = 12;
+    left.entities.push(common::entity("player", 1, (4., 4.)));
+    let mut right = common::level("a-01", (16, 0), (16, 16));
+    right.solids[(1, 0)] = '3';
+    right.triggers.push(strawberride::Entity { kind: EntityKind::Trigger, ..common::entity("windTrigger", 2, (0., 8.)) });
+    right.bg_decals.push(Decal { position: (8., 8.), ..Default::default() });
+    let mut map = common::map(vec![left, right]);
+
+    map.merge_levels(0, 1).unwrap();
+    assert_eq!(map.levels.len(), 1);
+
+    let merged = &map.levels[0];
+    assert_eq!(merged.name, "a-00");
+    assert_eq!(merged.data.position, (0, 0));
+    assert_eq!(merged.data.size, (32, 16));
+    assert_eq!(merged.solids.raw_data(), ['0', '0', '0', '3', '1', '0', '0', '0']);
+    assert_eq!(merged.fg_tiles.raw_data(), [-1, 12, -1, -1, -1, -1, -1, -1]);
+
+    // Everything from the second level is moved over by the width of the first
+    assert_eq!(merged.entities[0].position, (4., 4.));
+    assert_eq!(merged.triggers[0].position, (16., 8.));
+    assert_eq!(merged.bg_decals[0].position, (24., 8.));
+}
+
+#[test]
+fn merge_rejects_distant_levels() {
+    use strawberride::MergeError;
+
+    let mut map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (1000, 1000), (320, 184))
+    ]);
+    assert_eq!(map.merge_levels(0, 1), Err(MergeError::NotAdjacent));
+    assert_eq!(map.merge_levels(0, 0), Err(MergeError::SameLevel));
+    assert_eq!(map.merge_levels(0, 2), Err(MergeError::OutOfBounds(2)));
+    assert_eq!(map.levels.len(), 2);
+}
+
+#[test]
+fn failed_merges_leave_the_map_alone() {
+    use strawberride::{Level, Map, MergeError};
+
+    // Too far out to add the size to the position
+    let mut far = Level::new("far", 16, 16).unwrap();
+    far.data.position = (i32::MAX - 8, 0);
+    let near = Level::new("near", 16, 16).unwrap();
+    let mut map = Map { levels: vec![far, near], ..Default::default() };
+    let before = map.clone();
+    assert_eq!(map.merge_levels(0, 1), Err(MergeError::TooLarge));
+    assert_eq!(map, before);
+
+    // Adjacent, but the union is wider than a level can be
+    let mut left = Level::new("left", 16, 16).unwrap();
+    left.data.position = (i32::MIN, 0);
+    left.data.size = (i32::MAX, 16);
+    let mut right = Level::new("right", 16, 16).unwrap();
+    right.data.position = (-1, 0);
+    right.data.size = (8, 16);
+    let mut map = Map { levels: vec![left, right], ..Default::default() };
+    let before = map.clone();
+    assert!(map.merge_levels(0, 1).is_err());
+    assert_eq!(map, before);
+}
+
+#[test]
+fn sample_map_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+    let map = common::sample_map();
+    let bytes = common::store_bytes(map.clone());
+    assert_eq!(strawberride::Map::load(&mut bytes.as_slice(), true)?, map);
+    Ok(())
+}
+
+#[test]
+fn largest_attributes_finds_tilemaps() {
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    for y in 0..23 {
+        for x in (0..40).step_by(2) {
+            level.fg_tiles[(x, y)] = 100 + x as i32;
+        }
+    }
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    let map = common::map(vec![level]);
+
+    let largest = map.largest_attributes(3);
+    assert_eq!(largest.len(), 3);
+    assert_eq!(largest[0].0, "innerText");
+    assert!(largest.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+}
+
+#[test]
+fn load_many_reports_per_file() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("strawberride-load-many-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let good = dir.join("good.bin");
+    let garbage = dir.join("garbage.bin");
+    let missing = dir.join("missing.bin");
+    std::fs::write(&good, common::store_bytes(common::sample_map()))?;
+    std::fs::write(&garbage, b"not a celeste map at all")?;
+
+    let results = strawberride::load_many(&[good.clone(), garbage.clone(), missing.clone()], true);
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].0, good);
+    assert_eq!(results[0].1.as_ref().unwrap(), &common::sample_map());
+    assert_eq!(results[1].0, garbage);
+    assert!(results[1].1.is_err());
+    assert_eq!(results[2].0, missing);
+    assert!(results[2].1.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn flatten_nested_stylegrounds() {
+    use std::collections::HashMap;
+    use strawberride::{Element, Map, Value};
+
+    let parallax = |texture: &str| Element {
+        name: "parallax".into(),
+        attributes: HashMap::from([("texture".into(), Value::String(texture.into()))]),
+        children: vec![]
+    };
+    let apply = |children: Vec<Element>| Element {
+        name: "apply".into(),
+        attributes: HashMap::new(),
+        children
+    };
+
+    let map = Map {
+        foregrounds: vec![parallax("fg0")],
+        backgrounds: vec![
+            apply(vec![parallax("bg0"), apply(vec![parallax("bg1")])]),
+            parallax("bg2"),
+        ],
+        ..Default::default()
+    };
+
+    let textures: Vec<_> = map.flatten_stylegrounds()
+        .into_iter()
+        .map(|el| el.attributes["texture"].clone())
+        .collect();
+    assert_eq!(textures, ["fg0", "bg0", "bg1", "bg2"].map(|t| Value::String(t.into())));
+}
+
+#[test]
+fn mod_prefixes() {
+    use strawberride::Decal;
+
+    let decal = |texture: &str| Decal { texture: texture.into(), ..Default::default() };
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities.push(common::entity("player", 1, (16., 168.)));
+    first.entities.push(common::entity("FrostHelper/IceSpinner", 2, (64., 64.)));
+    first.fg_decals.push(decal("decals/1-forsakencity/flag.png"));
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.triggers.push(common::entity("CommunalHelper/MusicTrigger", 3, (0., 0.)));
+    second.bg_decals.push(decal("decals/generic/grass_a.png"));
+    second.fg_decals.push(decal("decals/VivHelper/lamp.png"));
+    let map = common::map(vec![first, second]);
+
+    let prefixes: Vec<_> = map.referenced_mod_prefixes().into_iter().collect();
+    assert_eq!(prefixes, ["CommunalHelper", "FrostHelper", "VivHelper"]);
+}
+
+#[test]
+fn patch_reproduces_new_map() {
+    use strawberride::{LevelListPatch, Map};
+
+    // 4x2 tiles each
+    let mut first = common::level("a-00", (0, 0), (32, 16));
+    first.entities.push(common::entity("spikesUp", 1, (8., 8.)));
+    let second = common::level("a-01", (32, 0), (32, 16));
+    let old = Map { bg_color: Some([0x10, 0x20, 0x30, 0xFF]), ..common::map(vec![first, second]) };
+
+    let mut new = old.clone();
+    new.levels[0].entities[0].position = (16., 8.);
+    new.levels[0].entities.push(common::entity("strawberry", 10, (0., 0.)));
+    new.levels[0].solids[(1, 1)] = '4';
+    new.levels[1].name = "a-02".into();
+    new.levels.swap(0, 1);
+    new.bg_color = None;
+
+    let patch = Map::create_patch(&old, &new);
+    assert!(patch.rest.is_some());
+    let Some(LevelListPatch::Edit { added, changed, .. }) = &patch.levels else {
+        panic!("levels should be edited, not replaced")
+    };
+    assert_eq!(added.len(), 1);
+    assert_eq!(changed.len(), 1);
+    assert!(changed[0].bg.is_none());
+    let solids = changed[0].solids.as_ref().expect("solids should be patched");
+    assert_eq!(solids.runs, [(4 + 1, vec!['4'])]);
+
+    let mut patched = old.clone();
+    patched.apply_patch(&patch);
+    assert_eq!(patched, new);
+
+    assert_eq!(Map::create_patch(&new, &new), Default::default());
+}
+
+#[test]
+fn patch_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+    use strawberride::{Decal, Map, MapPatch};
+
+    let mut first = common::level("a-00", (0, 0), (32, 16));
+    first.entities.push(common::entity("player", 1, (8., 8.)));
+    first.entities.push(common::entity("spikesUp", 2, (16., 8.)));
+    first.fg_decals.push(Decal { texture: "decals/1-forsakencity/flag.png".into(), ..Default::default() });
+    let second = common::level("a-01", (32, 0), (32, 16));
+    let old = common::map(vec![first, second]);
+
+    let mut new = old.clone();
+    new.levels[0].entities.retain(|entity| entity.id != 2);
+    new.levels[0].entities.push(common::entity("strawberry", 10, (0., 0.)));
+    new.levels[0].bg_tiles[(3, 1)] = 7;
+    new.levels[0].fg_decals.clear();
+    new.levels[1].name = "a-02".into();
+    new.bg_color = Some([1, 2, 3, 255]);
+    new.header = Some("FORK MAP".into());
+
+    for patch in [Map::create_patch(&old, &new), Map::create_patch(&new, &old), Default::default()] {
+        let mut bytes = Vec::new();
+        patch.store(&mut bytes)?;
+        assert_eq!(MapPatch::load(&mut bytes.as_slice())?, patch);
+    }
+
+    let mut bytes = Vec::new();
+    Map::create_patch(&old, &new).store(&mut bytes)?;
+    let mut patched = old.clone();
+    patched.apply_patch(&MapPatch::load(&mut bytes.as_slice())?);
+    assert_eq!(patched, new);
+
+    Ok(())
+}
+
+#[test]
+fn duplicate_entity_ids_are_flagged() {
+    let strawberry = |id| common::entity("strawberry", id, (0., 0.));
+
+    // Ids only have to be unique within a level
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities = vec![strawberry(1), strawberry(5)];
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.entities = vec![strawberry(5)];
+    let mut map = common::map(vec![first, second]);
+    assert!(map.duplicate_entity_ids().is_empty());
+
+    map.levels[1].entities.push(strawberry(5));
+    map.levels[1].triggers.push(strawberry(5));
+    assert_eq!(map.duplicate_entity_ids(), [(1, 5)]);
+}
+
+#[test]
+fn oversized_elements_are_reported() {
+    use strawberride::Value;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("spikesUp", 1, (64., 168.)));
+    let mut map = common::map(vec![level]);
+    assert!(map.oversized_elements().is_empty());
+
+    let spikes = &mut map.levels[0].entities[0];
+    for i in 0 .. 300 {
+        spikes.values.insert(format!("attr{i}"), Value::Integer(i));
+    }
+    assert_eq!(map.oversized_elements(), ["spikesUp"]);
+}
+
+#[test]
+fn canonicalized_maps_store_identically() {
+    use strawberride::{Filler, Value};
+
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities.push(common::entity("player", 1, (16., 168.)));
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+    let mut a = common::map(vec![first, second]);
+    a.filler = vec![Filler { position: (80, 0), size: (4, 4) }, Filler { position: (40, 0), size: (10, 10) }];
+    a.levels[0].entities[0].values.insert("sprite".into(), Value::RleString("madeline".into()));
+
+    let mut b = a.clone();
+    b.levels.reverse();
+    b.filler.reverse();
+    b.levels[1].entities[0].values.insert("sprite".into(), Value::String("madeline".into()));
+    b.levels[1].entities[0].values.insert("x".into(), Value::Integer(-5));
+    assert_ne!(common::store_bytes(a.clone()), common::store_bytes(b.clone()));
+
+    a.canonicalize();
+    b.canonicalize();
+    assert_eq!(common::store_bytes(a), common::store_bytes(b));
+}
+
+#[test]
+fn unknown_value_types_load_leniently() {
+    use strawberride::{LoadError, LoadOptions, Map, Value};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities[0].values.insert("future".into(), Value::Unknown(9, vec![1, 2, 3]));
+    let map = common::map(vec![level]);
+    let bytes = common::store_bytes(map.clone());
+
+    assert!(matches!(Map::load(&mut &bytes[..], true), Err(LoadError::InvalidValueType(9))));
+
+    let options = LoadOptions { lenient_values: true, ..Default::default() };
+    let loaded = Map::load_with_options(&mut &bytes[..], options).unwrap();
+    assert_eq!(loaded, map);
+    assert_eq!(common::store_bytes(loaded), bytes);
+}
+
+#[test]
+fn custom_empty_tiles() {
+    use strawberride::{Level, LoadOptions, Map};
+
+    let mut level = Level::new("a", 24, 8).unwrap();
+    level.solids[(1, 0)] = '1';
+    level.fg_tiles[(0, 0)] = 5;
+    let bytes = common::store_bytes(Map { levels: vec![level], ..Default::default() });
+
+    let options = LoadOptions { empty_char_tile: 'x', empty_int_tile: -2, ..Default::default() };
+    let map = Map::load_with_options(&mut &bytes[..], options).unwrap();
+    let level = &map.levels[0];
+    // The stored row is "01", so the `0` is a real tile and the missing cell is empty
+    assert_eq!(level.solids.raw_data(), ['0', '1', 'x']);
+    assert_eq!(level.solids.empty(), 'x');
+    assert_eq!(level.bg.raw_data(), ['x'; 3]);
+    assert_eq!(level.fg_tiles.raw_data(), [5, -2, -2]);
+    assert_eq!(level.fg_tiles.empty(), -2);
+
+    assert_eq!(common::store_bytes(map), bytes);
+}
+
+#[test]
+fn unknown_values_with_known_tags_are_not_stored() {
+    use strawberride::{Entity, Level, Map, Value};
+
+    let mut level = Level::new("a", 8, 8).unwrap();
+    level.entities.push(Entity {
+        name: "mod/thing".into(),
+        values: [("fake".into(), Value::Unknown(5, vec![0, 0]))].into(),
+        ..Default::default()
+    });
+    let map = Map { levels: vec![level], ..Default::default() };
+    assert!(map.store(&mut Vec::new(), true).is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn async_round_trip() {
+    use strawberride::Map;
+
+    let map = common::sample_map();
+    let mut buf = Vec::new();
+    map.clone().store_async(&mut buf, true).await.unwrap();
+    assert_eq!(buf, common::store_bytes(map.clone()));
+
+    let loaded = Map::load_async(&mut &buf[..], true).await.unwrap();
+    assert_eq!(loaded, map);
+}
+
+#[test]
+fn undersized_levels_expand_to_fit_tilemaps() {
+    use strawberride::{Element, LoadOptions, Map, Value};
+
+    let mut root = Element::from(common::map(vec![common::level("a-00", (0, 0), (320, 184))]));
+    let levels = root.children.iter_mut().find(|el| &*el.name == "levels").unwrap();
+    let solids = levels.children[0].children.iter_mut().find(|el| &*el.name == "solids").unwrap();
+    // Declared as 40 tiles wide, but this row is 45 long
+    solids.attributes.insert("innerText".into(), Value::RleString(format!("{}{}", "0".repeat(40), "12345")));
+    let bytes = root.to_map_bytes("sample", true).unwrap();
+
+    let strict = Map::load(&mut &bytes[..], true).unwrap();
+    assert_eq!(strict.levels[0].solids.width(), 40);
+
+    let options = LoadOptions { expand_tilemaps: true, ..Default::default() };
+    let expanded = Map::load_with_options(&mut &bytes[..], options).unwrap();
+    let level = &expanded.levels[0];
+    assert_eq!(level.data.size, (360, 184));
+    assert_eq!(level.solids.width(), 45);
+    assert_eq!(level.solids.height(), 23);
+    assert_eq!(level.solids[(44, 0)], '5');
+}
+
+#[test]
+fn level_neighbors_by_direction() {
+    use strawberride::{Direction, Map};
+
+    let mut map = Map::default();
+    for (name, position) in [("tl", (0, 0)), ("tr", (320, 0)), ("bl", (0, 184)), ("br", (320, 184))] {
+        map.levels.push(common::level(name, position, (320, 184)));
+    }
+    let neighbor = |from: usize, direction| map.level_in_direction(&map.levels[from], direction).map(|l| l.name.as_str());
+
+    assert_eq!(neighbor(0, Direction::Right), Some("tr"));
+    assert_eq!(neighbor(0, Direction::Down), Some("bl"));
+    assert_eq!(neighbor(0, Direction::Left), None);
+    assert_eq!(neighbor(0, Direction::Up), None);
+    assert_eq!(neighbor(3, Direction::Left), Some("bl"));
+    assert_eq!(neighbor(3, Direction::Up), Some("tr"));
+    assert_eq!(neighbor(1, Direction::Down), Some("br"));
+    assert_eq!(neighbor(2, Direction::Right), Some("br"));
+}
+
+#[test]
+fn level_neighbors_near_the_ends_of_the_coordinate_range() {
+    use strawberride::Direction;
+
+    let map = common::map(vec![
+        common::level("left", (i32::MAX - 640, 0), (320, 184)),
+        // Its right edge is past i32::MAX
+        common::level("right", (i32::MAX - 320, 0), (400, 184)),
+        common::level("bottom", (i32::MAX - 320, 184), (400, 184)),
+        common::level("min", (i32::MIN, i32::MIN), (320, 184)),
+        common::level("below min", (i32::MIN, i32::MIN + 184), (320, 184))
+    ]);
+    let neighbor = |from: usize, direction| map.level_in_direction(&map.levels[from], direction).map(|l| l.name.as_str());
+
+    assert_eq!(neighbor(0, Direction::Right), Some("right"));
+    assert_eq!(neighbor(1, Direction::Left), Some("left"));
+    assert_eq!(neighbor(1, Direction::Right), None);
+    assert_eq!(neighbor(1, Direction::Down), Some("bottom"));
+    assert_eq!(neighbor(2, Direction::Up), Some("right"));
+    assert_eq!(neighbor(3, Direction::Down), Some("below min"));
+    assert_eq!(neighbor(4, Direction::Up), Some("min"));
+    assert_eq!(neighbor(3, Direction::Left), None);
+}
+
+#[test]
+fn string_values_are_enumerated_with_paths() {
+    use strawberride::Decal;
+
+    let mut level = common::level("a-01", (0, 0), (320, 184));
+    level.fg_decals.push(Decal { texture: "decals/1-forsakencity/flag.png".into(), ..Default::default() });
+    let map = common::map(vec![level]);
+    let strings = map.all_string_values();
+
+    let (path, _) = strings.iter()
+        .find(|(_, string)| *string == "decals/1-forsakencity/flag.png")
+        .expect("decal texture should be found");
+    assert_eq!(path, &["Map", "levels", "level", "fgdecals", "decal", "texture"]);
+    assert!(strings.iter().any(|(path, string)| *string == "a-01" && path.last().unwrap() == "name"));
+}
+
+#[test]
+fn string_values_are_replaced() {
+    use strawberride::{Decal, Element, Value};
+
+    let decal = |texture: &str| Decal { texture: texture.into(), ..Default::default() };
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.solids[(0, 0)] = '1';
+    first.fg_decals.push(decal("decals/1-forsakencity/flag.png"));
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.fg_decals.push(decal("decals/1-forsakencity/bench.png"));
+    let mut map = common::map(vec![first, second]);
+    map.extra_children.push(Element {
+        name: "notes".into(),
+        attributes: [("innerText".into(), Value::String("decals/1-forsakencity/".into()))].into(),
+        children: vec![]
+    });
+    let solids = map.levels[0].solids.clone();
+
+    assert_eq!(map.replace_string_values("decals/1-forsakencity/", "decals/mymod/"), 2);
+    assert_eq!(map.levels[0].fg_decals[0].texture, "decals/mymod/flag.png");
+    assert_eq!(map.levels[1].fg_decals[0].texture, "decals/mymod/bench.png");
+    assert_eq!(map.extra_children[0].inner_text(), Some("decals/1-forsakencity/"));
+    assert_eq!(map.levels[0].solids, solids);
+    assert_eq!(map.replace_string_values("", "x"), 0);
+}
+
+#[test]
+fn start_level_is_found_by_spawn() {
+    let mut map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (320, 0), (320, 184))
+    ]);
+    // Without a player anywhere, the first level is used
+    assert_eq!(map.start_level().unwrap().name, "a-00");
+
+    map.levels[1].entities.push(common::entity("player", 1, (16., 168.)));
+    assert_eq!(map.start_level().unwrap().name, "a-01");
+
+    map.levels[0].entities.push(common::entity("player", 2, (16., 168.)));
+    assert_eq!(map.start_level().unwrap().name, "a-00");
+}
+
+#[test]
+fn non_finite_floats_are_caught_before_writing() {
+    use strawberride::{Decal, Map, StoreError, StoreOptions};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.fg_decals.push(Decal { rotation: f32::NAN, ..Default::default() });
+    let map = common::map(vec![level]);
+
+    let mut buf = Vec::new();
+    let err = map.clone().store_with_options(&mut buf, StoreOptions::default()).unwrap_err();
+    assert!(matches!(
+        &err,
+        StoreError::NonFiniteFloat { element, key } if element == "decal" && key == "rotation"
+    ));
+    assert!(buf.is_empty());
+    assert!(map.clone().store(&mut buf, true).is_err());
+
+    let options = StoreOptions { clamp_non_finite_floats: true, ..Default::default() };
+    map.clone().store_with_options(&mut buf, options).unwrap();
+    let loaded = Map::load(&mut &buf[..], true).unwrap();
+    assert_eq!(loaded.levels[0].fg_decals[0].rotation, 0.0);
+}
+
+#[test]
+fn bg_color_hex_strings() {
+    use strawberride::Map;
+
+    let mut map = Map { bg_color: Some([0x10, 0x20, 0x30, 0xFF]), ..Default::default() };
+    assert_eq!(map.bg_color_hex().as_deref(), Some("102030ff"));
+
+    map.set_bg_color_hex("#A0B0C0").unwrap();
+    assert_eq!(map.bg_color, Some([0xA0, 0xB0, 0xC0, 0xFF]));
+    map.set_bg_color_hex("01020304").unwrap();
+    assert_eq!(map.bg_color_hex().as_deref(), Some("01020304"));
+
+    assert!(map.set_bg_color_hex("12345").is_err());
+    assert!(map.set_bg_color_hex("zz0000").is_err());
+    assert_eq!(map.bg_color, Some([1, 2, 3, 4]));
+
+    map.bg_color = None;
+    assert_eq!(map.bg_color_hex(), None);
+}
+
+#[test]
+fn entities_and_triggers_are_classified_by_container() {
+    use strawberride::{EntityKind, LoadError, LoadOptions, Map};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.triggers.push(strawberride::Entity { kind: EntityKind::Trigger, ..common::entity("windTrigger", 2, (0., 0.)) });
+    let mut map = common::map(vec![level]);
+    assert!(map.levels[0].triggers[0].looks_like_trigger());
+    assert!(!map.levels[0].entities[0].looks_like_trigger());
+
+    let strict = LoadOptions { strict_entity_containers: true, ..Default::default() };
+    assert!(Map::load_with_options(&mut &common::store_bytes(map.clone())[..], strict).is_ok());
+
+    map.levels[0].entities.push(common::entity("cameraOffsetTrigger", 3, (0., 0.)));
+    let bytes = common::store_bytes(map);
+
+    let loaded = Map::load(&mut &bytes[..], true).unwrap();
+    assert_eq!(loaded.levels[0].entities[1].name, "cameraOffsetTrigger");
+    assert_eq!(loaded.levels[0].triggers.len(), 1);
+
+    assert!(matches!(
+        Map::load_with_options(&mut &bytes[..], strict),
+        Err(LoadError::InvalidFieldData("entities", _))
+    ));
+}
+
+#[test]
+fn misplaced_entities_are_warned_about() -> Result<(), Box<dyn std::error::Error>> {
+    use strawberride::{Entity, Level, LoadError, LoadOptions, LoadWarning, Map};
+
+    let mut level = Level::new("a", 320, 184).unwrap();
+    for (id, name) in [(1, "triggerSpikesUp"), (2, "MaxHelpingHand/FlagTrigger"), (3, "cameraOffsetTrigger")] {
+        level.entities.push(Entity { name: name.into(), id, ..Default::default() });
+    }
+    level.triggers.push(Entity { name: "lookoutBlocker".into(), id: 4, ..Default::default() });
+    level.triggers.push(Entity { name: "Mod/TriggerSpikes".into(), id: 5, ..Default::default() });
+    let bytes = common::store_bytes(Map { levels: vec![level], ..Default::default() });
+
+    let (_, warnings) = Map::load_with_warnings(&mut &bytes[..], LoadOptions::default())?;
+    let misplaced: Vec<_> = warnings.iter().map(|warning| match warning {
+        LoadWarning::MisplacedEntity { id, in_triggers, .. } => (*id, *in_triggers),
+        _ => unreachable!()
+    }).collect();
+    assert_eq!(misplaced, [(2, false), (3, false), (5, true)]);
+
+    let strict = LoadOptions { strict_entity_containers: true, ..Default::default() };
+    let Err(LoadError::InvalidFieldData("entities", message)) = Map::load_with_options(&mut &bytes[..], strict) else {
+        panic!("misplaced entities should fail a strict load")
+    };
+    for name in ["MaxHelpingHand/FlagTrigger", "cameraOffsetTrigger", "Mod/TriggerSpikes"] {
+        assert!(message.contains(name), "{message:?} should mention {name}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sorted_extra_children_store_identically() {
+    use std::collections::HashMap;
+    use strawberride::{Element, StoreOptions, Value};
+
+    let child = |name: &str, n| Element {
+        name: name.into(),
+        attributes: HashMap::from([("n".into(), Value::Integer(n))]),
+        children: vec![]
+    };
+    let mut forwards = common::map(vec![common::level("a-00", (0, 0), (320, 184))]);
+    forwards.extra_children = vec![child("b", 1), child("a", 2), child("b", 3)];
+    forwards.levels[0].extra_children = vec![child("zeta", 1), child("alpha", 2)];
+    let mut backwards = forwards.clone();
+    backwards.extra_children = vec![child("b", 1), child("b", 3), child("a", 2)];
+    backwards.levels[0].extra_children.reverse();
+
+    let options = StoreOptions { sort_extra_children: true, ..Default::default() };
+    let store = |map: strawberride::Map| {
+        let mut buf = Vec::new();
+        map.store_with_options(&mut buf, options).unwrap();
+        buf
+    };
+    assert_ne!(common::store_bytes(forwards.clone()), common::store_bytes(backwards.clone()));
+    assert_eq!(store(forwards.clone()), store(backwards));
+
+    forwards.sort_extra_children();
+    let names: Vec<_> = forwards.extra_children.iter().map(|el| (&*el.name, el.attributes["n"].clone())).collect();
+    assert_eq!(names, [("a", Value::Integer(2)), ("b", Value::Integer(1)), ("b", Value::Integer(3))]);
+}
+
+#[test]
+fn extra_style_elements_are_kept() {
+    use std::{collections::HashMap, sync::Arc};
+    use strawberride::{Element, Map, Value};
+
+    let el = |name: &str, attributes: HashMap<Arc<str>, Value>, children| Element { name: name.into(), attributes, children };
+    let style = |color: &str, styleground: &str| el(
+        "Style",
+        HashMap::from([("color".into(), Value::String(color.into()))]),
+        vec![el("Backgrounds", HashMap::new(), vec![el(styleground, HashMap::new(), vec![])])]
+    );
+    let root = el("Map", HashMap::new(), vec![
+        style("112233", "parallax"),
+        el("levels", HashMap::new(), vec![]),
+        style("445566", "stars")
+    ]);
+
+    let map = Map::try_from(root).unwrap();
+    assert_eq!(map.bg_color, Some([0x44, 0x55, 0x66, 0xFF]));
+    assert_eq!(&*map.backgrounds[0].name, "stars");
+    assert_eq!(map.extra_children, [style("112233", "parallax")]);
+
+    let loaded = Map::load(&mut &common::store_bytes(map.clone())[..], true).unwrap();
+    assert_eq!(loaded, map);
+}
+
+#[test]
+fn omit_defaults_shrinks_levels() {
+    use strawberride::{Element, Map, StoreOptions};
+
+    let mut map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (320, 0), (320, 184))
+    ]);
+    map.levels[1].data.dark = true;
+    map.levels[1].data.music_progress = Some(2);
+
+    let store = |omit_defaults| {
+        let mut buf = Vec::new();
+        map.clone().store_with_options(&mut buf, StoreOptions { omit_defaults, ..Default::default() }).unwrap();
+        buf
+    };
+    let (full, omitted) = (store(false), store(true));
+    assert!(omitted.len() < full.len());
+
+    let level_attrs = |bytes: &[u8]| {
+        let root = Element::from_map_bytes(bytes, true).unwrap();
+        let levels = root.children.into_iter().find(|child| &*child.name == "levels").unwrap();
+        levels.children.into_iter().map(|level| level.attributes).collect::<Vec<_>>()
+    };
+    let (full_attrs, omitted_attrs) = (level_attrs(&full), level_attrs(&omitted));
+    assert!(omitted_attrs[0].len() + 15 <= full_attrs[0].len());
+    assert!(omitted_attrs[0].contains_key("width") && !omitted_attrs[0].contains_key("underwater"));
+    assert!(omitted_attrs[1].contains_key("dark") && omitted_attrs[1].contains_key("musicProgress"));
+
+    assert_eq!(Map::load(&mut &omitted[..], true).unwrap(), map);
+}
+
+#[test]
+fn trailing_data() {
+    use strawberride::{LoadError, LoadOptions, Map};
+
+    let map = common::sample_map();
+    let mut bytes = common::store_bytes(map.clone());
+    let strict = LoadOptions { reject_trailing_data: true, ..Default::default() };
+    assert_eq!(Map::load_with_options(&mut &bytes[..], strict).unwrap(), map);
+
+    bytes.extend_from_slice(b"metadata");
+    assert!(matches!(Map::load_with_options(&mut &bytes[..], strict), Err(LoadError::TrailingData(8))));
+
+    let mut stream = &bytes[..];
+    assert_eq!(Map::load(&mut stream, true).unwrap(), map);
+    assert_eq!(stream, b"metadata");
+}
+
+#[test]
+fn lossy_utf8() {
+    use strawberride::{LoadError, LoadOptions, Map, Value};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    // The entity name goes in the lookup table, and the value is stored inline
+    level.entities.push(common::entity("caf\u{e9}", 1, (0., 0.)));
+    level.entities[0].values.insert("message".into(), Value::String("caf\u{e9}".into()));
+    let mut bytes = common::store_bytes(common::map(vec![level]));
+    // Break the first byte of every `é`, keeping the lengths the same
+    for i in 0 .. bytes.len() - 1 {
+        if bytes[i .. i + 2] == [0xc3, 0xa9] {
+            bytes[i] = 0xff;
+        }
+    }
+
+    assert!(matches!(Map::load(&mut &bytes[..], true), Err(LoadError::IoError(_))));
+
+    let lossy = LoadOptions { lossy_utf8: true, ..Default::default() };
+    let map = Map::load_with_options(&mut &bytes[..], lossy).unwrap();
+    let entity = &map.levels[0].entities[0];
+    assert_eq!(entity.name, "caf\u{fffd}\u{fffd}");
+    assert_eq!(entity.values["message"], Value::String("caf\u{fffd}\u{fffd}".into()));
+}
+
+#[test]
+fn total_tile_cells() {
+    let mut map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (320, 0), (320, 184))
+    ]);
+    // Both levels are 40x23 tiles, with five tilemaps each
+    assert_eq!(map.total_tile_cells(), 2 * 5 * 40 * 23);
+
+    map.levels[1].resize(80, 16);
+    assert_eq!(map.total_tile_cells(), 5 * 40 * 23 + 5 * 10 * 2);
+    map.levels.clear();
+    assert_eq!(map.total_tile_cells(), 0);
+}
+
+#[test]
+fn booleans_stored_as_strings() {
+    use strawberride::{Element, Level, Value};
+
+    let mut el = Element::from(common::level("a-00", (0, 0), (320, 184)));
+    el.attributes.insert("dark".into(), Value::String("true".into()));
+    el.attributes.insert("underwater".into(), Value::RleString("FALSE".into()));
+    el.attributes.insert("space".into(), Value::String("yes".into()));
+
+    let level = Level::try_from(el).unwrap();
+    assert!(level.data.dark);
+    assert!(!level.data.underwater);
+    // Anything that doesn't parse falls back to the default
+    assert!(!level.data.space);
+}
+
+#[test]
+fn debug_output_skips_tiles() {
+    use strawberride::Decal;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    for x in 0..40 {
+        level.solids[(x, 22)] = '1';
+    }
+    level.entities.push(common::entity("spikesUp", 1, (64., 168.)));
+    level.fg_decals.push(Decal { texture: "decals/1-forsakencity/flag.png".into(), ..Default::default() });
+    let map = common::map(vec![level]);
+
+    for debug in [format!("{map:?}"), format!("{map:#?}")] {
+        assert!(debug.len() < 20_000, "debug output was {} bytes", debug.len());
+        assert!(debug.contains("\"a-00\"") && debug.contains("Tilemap 40x23"));
+        assert!(debug.contains("spikesUp") && debug.contains("flag.png"));
+        assert!(!debug.contains("1111111111"));
+    }
+}
+
+#[test]
+fn entity_kinds() {
+    use strawberride::{Element, EntityKind, Map};
+
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities_of_mut(EntityKind::Entity).push(common::entity("player", 1, (16., 168.)));
+    first.entities_of_mut(EntityKind::Entity).push(common::entity("spikesUp", 2, (64., 168.)));
+    first.entities_of_mut(EntityKind::Trigger).push(common::entity("windTrigger", 3, (0., 0.)));
+    let mut second = common::level("a-01", (320, 0), (320, 184));
+    second.entities_of_mut(EntityKind::Trigger).push(common::entity("musicTrigger", 40, (0., 0.)));
+    second.entities_of_mut(EntityKind::Entity).push(common::entity("refill", 41, (0., 0.)));
+
+    let loaded = Map::load(&mut &common::store_bytes(common::map(vec![first, second]))[..], true).unwrap();
+    let kinds = |level: usize| loaded.levels[level].all_entities()
+        .map(|(kind, entity)| (kind, entity.id))
+        .collect::<Vec<_>>();
+    assert_eq!(kinds(0), [(EntityKind::Entity, 1), (EntityKind::Entity, 2), (EntityKind::Trigger, 3)]);
+    assert_eq!(kinds(1), [(EntityKind::Entity, 41), (EntityKind::Trigger, 40)]);
+    assert_eq!(loaded.levels[1].entities_of(EntityKind::Trigger)[0].name, "musicTrigger");
+    for (kind, entity) in loaded.levels.iter().flat_map(|level| level.all_entities()) {
+        assert_eq!(entity.kind, kind);
+    }
+
+    // Entities taken out of a level remember what they were, and go back where they came from
+    let mut level = loaded.levels[0].clone();
+    let taken: Vec<_> = level.entities.drain(..).chain(level.triggers.drain(..)).collect();
+    for entity in taken {
+        level.push_entity(entity);
+    }
+    assert_eq!(level, loaded.levels[0]);
+
+    let level = Element::from(loaded.levels[1].clone());
+    for kind in [EntityKind::Entity, EntityKind::Trigger] {
+        let container = level.children.iter().find(|child| &*child.name == kind.container_name()).unwrap();
+        assert_eq!(container.children.len(), 1);
+    }
+}
+
+#[test]
+fn translate_filler() {
+    use strawberride::{Filler, Map};
+
+    let mut map = Map {
+        filler: vec![
+            Filler { position: (0, 0), size: (4, 4) },
+            Filler { position: (-10, 25), size: (2, 8) }
+        ],
+        ..Default::default()
+    };
+    assert!(map.translate_filler(5, -3));
+    assert_eq!(map.filler, [
+        Filler { position: (5, -3), size: (4, 4) },
+        Filler { position: (-5, 22), size: (2, 8) }
+    ]);
+
+    // Only the second rectangle would overflow, but neither of them moves
+    let before = map.filler.clone();
+    assert!(!map.translate_filler(0, i32::MAX - 10));
+    assert!(!map.translate_filler(i32::MIN + 2, 0));
+    assert_eq!(map.filler, before);
+}
+
+#[test]
+fn duplicate_level_names() {
+    let mut map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (320, 0), (320, 184))
+    ]);
+    assert!(map.duplicate_level_names().is_empty());
+
+    for _ in 0 .. 2 {
+        map.levels.push(common::level("a", (0, 400), (320, 184)));
+    }
+    map.levels.push(common::level("a-00", (400, 400), (320, 184)));
+    assert_eq!(map.duplicate_level_names(), ["a", "a-00"]);
+}
+
+#[test]
+fn load_progress() {
+    use strawberride::{Element, LoadPhase, Map};
+
+    fn count(el: &Element) -> usize {
+        1 + el.children.iter().map(count).sum::<usize>()
+    }
+
+    let map = common::sample_map();
+    let bytes = common::store_bytes(map.clone());
+    let mut phases = Vec::new();
+    let loaded = Map::load_with_progress(&mut &bytes[..], true, |phase| phases.push(phase)).unwrap();
+    assert_eq!(loaded, map);
+
+    let elements = count(&Element::from_map_bytes(&bytes, true).unwrap());
+    let table = map.compute_string_table().len();
+    assert_eq!(phases, [LoadPhase::HeaderRead, LoadPhase::LookupTableRead(table), LoadPhase::ElementsDecoded(elements)]);
+
+    // Bigger maps report as they go
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.extend((0 .. 2500).map(|id| common::entity("strawberry", id, (0., 0.))));
+    let bytes = common::store_bytes(common::map(vec![level]));
+    let elements = count(&Element::from_map_bytes(&bytes, true).unwrap());
+    let mut decoded = Vec::new();
+    Map::load_with_progress(&mut &bytes[..], true, |phase| if let LoadPhase::ElementsDecoded(count) = phase {
+        decoded.push(count);
+    }).unwrap();
+    assert_eq!(decoded, [1000, 2000, elements]);
+}
+
+#[test]
+fn custom_headers() {
+    use strawberride::{LoadError, Map};
+
+    let map = common::sample_map();
+    let mut bytes = vec![b"FORK MAP".len() as u8];
+    bytes.extend_from_slice(b"FORK MAP");
+    map.clone().store(&mut bytes, false).unwrap();
+
+    let forked = Map::load_with_header(&mut &bytes[..], "FORK MAP").unwrap();
+    assert_eq!(forked.header.as_deref(), Some("FORK MAP"));
+    assert_eq!(Map { header: None, ..forked.clone() }, map);
+    assert!(matches!(Map::load_with_header(&mut &bytes[..], "CELESTE MAP"), Err(LoadError::InvalidHeader(header)) if header == "FORK MAP"));
+    assert!(Map::load(&mut &bytes[..], true).is_err());
+
+    let mut stream = &bytes[..];
+    assert_eq!(Map::read_header(&mut stream).unwrap(), "FORK MAP");
+    assert_eq!(Map::load(&mut stream, false).unwrap(), map);
+
+    let vanilla = common::store_bytes(map.clone());
+    assert_eq!(Map::load_with_header(&mut &vanilla[..], "CELESTE MAP").unwrap(), map);
+
+    // The captured header is written back out when storing with a header
+    let mut stored = Vec::new();
+    forked.clone().store(&mut stored, true).unwrap();
+    assert_eq!(stored, bytes);
+    let mut streamed = Vec::new();
+    forked.store_streaming(&mut streamed, true).unwrap();
+    assert_eq!(streamed, bytes);
+}
+
+#[test]
+fn streaming_store_matches_buffered() {
+    use std::collections::HashMap;
+
+    use strawberride::{Element, Map};
+
+    let extra = |name: &str| Element {
+        name: name.into(),
+        attributes: HashMap::new(),
+        children: vec![]
+    };
+
+    let mut map = common::sample_map();
+    // Short tilemaps end up in the string table, and are shared between levels
+    for (i, name) in ["b-00", "b-01"].into_iter().enumerate() {
+        let mut level = common::level(name, (0, 200 + i as i32 * 8), (16, 8));
+        level.fg_tiles[(1, 0)] = 3;
+        level.obj_tiles[(0, 0)] = 7;
+        level.extra_children.push(extra("cameraOffset"));
+        map.levels.push(level);
+    }
+    map.extra_children.push(extra("Meta"));
+
+    let mut streamed = Vec::new();
+    map.clone().store_streaming(&mut streamed, true).unwrap();
+    assert_eq!(streamed, common::store_bytes(map.clone()));
+    assert_eq!(Map::load(&mut streamed.as_slice(), true).unwrap(), map);
+}
+
+#[test]
+fn level_combinators() {
+    let flip = |level: &mut strawberride::Level| {
+        level.data.dark = !level.data.dark;
+        level.solids.map_cells(|cell| if cell == '0' { '1' } else { '0' });
+    };
+    let levels = || {
+        let mut first = common::level("a-00", (0, 0), (32, 16));
+        first.solids[(0, 0)] = '1';
+        vec![first, common::level("a-01", (32, 0), (32, 16))]
+    };
+
+    let mut map = common::map(levels());
+    map.for_each_level_mut(flip);
+    assert!(map.levels.iter().all(|level| level.data.dark));
+    assert_eq!(map.levels[0].solids.raw_data(), ['0', '1', '1', '1', '1', '1', '1', '1']);
+    assert_eq!(map.levels[1].solids.non_empty_count(), 8);
+
+    let renamed = common::map(levels()).map_levels(|mut level| {
+        level.name = format!("lvl_{}", level.name);
+        level
+    });
+    assert_eq!(renamed.levels.iter().map(|level| level.name.as_str()).collect::<Vec<_>>(), ["lvl_a-00", "lvl_a-01"]);
+
+    #[cfg(feature = "rayon")]
+    {
+        let mut parallel = common::map(levels());
+        parallel.par_for_each_level_mut(flip);
+        assert_eq!(parallel, map);
+    }
+}
+
+#[test]
+fn level_index() {
+    let map = common::map(vec![
+        common::level("a-00", (0, 0), (320, 184)),
+        common::level("a-01", (320, 0), (320, 184)),
+        common::level("b-00", (0, 400), (320, 184)),
+        common::level("a-01", (400, 400), (320, 184))
+    ]);
+
+    let index = map.build_level_index();
+    assert_eq!(index.len(), 3);
+    assert_eq!(index["a-00"], 0);
+    // The first level with a name wins
+    assert_eq!(index["a-01"], 1);
+    assert_eq!(index["b-00"], 2);
+    assert!(!index.contains_key("c-00"));
+}
+
+#[test]
+fn partial_loads_keep_complete_levels() {
+    use strawberride::{LoadError, Map};
+
+    let map = common::sample_map();
+    let bytes = common::store_bytes(map.clone());
+
+    let (loaded, errors) = Map::load_partial(&mut &bytes[..], true);
+    assert_eq!(loaded.as_ref(), Some(&map));
+    assert!(errors.is_empty());
+
+    // Levels are stored last, so this cuts off the end of the second level
+    let (loaded, errors) = Map::load_partial(&mut &bytes[.. bytes.len() - 10], true);
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.levels, [map.levels[0].clone()]);
+    assert_eq!(loaded.bg_color, map.bg_color);
+    assert!(matches!(errors[..], [LoadError::IoError(ref err)] if err.kind() == std::io::ErrorKind::UnexpectedEof));
+    assert!(Map::load(&mut &bytes[.. bytes.len() - 10], true).is_err());
+
+    let (loaded, errors) = Map::load_partial(&mut &bytes[.. 5], true);
+    assert!(loaded.is_none());
+    assert_eq!(errors.len(), 1);
+}
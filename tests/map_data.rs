@@ -0,0 +1,648 @@
+mod common;
+
+use strawberride::{LevelData, WindPattern};
+
+#[test]
+fn new_levels_are_sized_in_tiles() {
+    use strawberride::Level;
+
+    let level = Level::new("tiles", 320, 184).unwrap();
+    assert_eq!(level.data.size, (320, 184));
+    for (width, height) in [
+        (level.bg.width(), level.bg.height()),
+        (level.solids.width(), level.solids.height()),
+        (level.bg_tiles.width(), level.bg_tiles.height()),
+        (level.fg_tiles.width(), level.fg_tiles.height()),
+        (level.obj_tiles.width(), level.obj_tiles.height())
+    ] {
+        assert_eq!((width, height), (40, 23));
+    }
+}
+
+#[test]
+fn levels_narrower_than_a_tile_can_be_resized() {
+    use strawberride::Level;
+
+    let mut level = Level::new("a", 0, 16).unwrap();
+    assert_eq!((level.solids.width(), level.solids.height()), (0, 2));
+
+    assert!(level.resize(16, 16));
+    assert_eq!(level.tile_size(), (2, 2));
+    assert_eq!(level.solids.raw_data(), ['0'; 4]);
+    assert_eq!(level.fg_tiles.raw_data(), [-1; 4]);
+}
+
+#[test]
+fn wind_pattern_parsing() {
+    let mut data = LevelData {
+        wind_pattern: "leftstrong".into(),
+        ..Default::default()
+    };
+    assert_eq!(data.wind_pattern_parsed().unwrap(), WindPattern::LeftStrong);
+    assert_eq!(WindPattern::LeftStrong.to_string(), "LeftStrong");
+
+    data.wind_pattern = "Sideways".into();
+    assert!(data.wind_pattern_parsed().is_err());
+}
+
+#[test]
+fn level_counts() {
+    use strawberride::Decal;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+    level.triggers.push(common::entity("windTrigger", 3, (0., 0.)));
+    level.fg_decals.push(Decal::default());
+    assert_eq!(level.entity_count(), 2);
+    assert_eq!(level.trigger_count(), 1);
+    assert_eq!(level.bg_decal_count(), 0);
+    assert_eq!(level.fg_decal_count(), 1);
+}
+
+#[test]
+fn decal_rotation_normalization() {
+    use strawberride::{Decal, Level};
+
+    let mut decal = Decal { rotation: 370.0, ..Default::default() };
+    decal.normalize_rotation();
+    assert_eq!(decal.rotation, 10.0);
+
+    let mut level = Level::new("rotations", 64, 64).unwrap();
+    level.bg_decals.push(Decal { rotation: -90.0, ..Default::default() });
+    level.fg_decals.push(Decal { rotation: 720.0, ..Default::default() });
+    level.normalize_all_rotations();
+    assert_eq!(level.bg_decals[0].rotation, 270.0);
+    assert_eq!(level.fg_decals[0].rotation, 0.0);
+}
+
+#[test]
+fn entity_grid_snapping() {
+    use strawberride::{Entity, Level};
+
+    let mut level = Level::new("snapping", 64, 64).unwrap();
+    level.entities.push(Entity {
+        position: (13.0, 7.0),
+        nodes: vec![(3.0, 21.0)],
+        ..Default::default()
+    });
+    assert!(level.snap_all_entities(8.0));
+    assert_eq!(level.entities[0].position, (16.0, 8.0));
+    assert_eq!(level.entities[0].nodes, [(0.0, 24.0)]);
+
+    let mut entity = Entity { position: (13.0, 7.0), nodes: vec![(3.0, 21.0)], ..Default::default() };
+    for grid in [0.0, -8.0, f32::NAN, f32::INFINITY] {
+        assert!(!entity.snap_to_grid(grid), "{grid}");
+        assert_eq!(entity.position, (13.0, 7.0));
+        assert_eq!(entity.nodes, [(3.0, 21.0)]);
+    }
+    assert!(!level.snap_all_entities(0.0));
+    assert_eq!(level.entities[0].position, (16.0, 8.0));
+}
+
+#[test]
+fn entity_size_rejects_negatives() {
+    use strawberride::{Element, Entity};
+
+    let mut entity = Entity::default();
+    assert!(entity.set_size(Some(16), Some(8)));
+    assert!(!entity.set_size(Some(-8), None));
+    assert_eq!((entity.width, entity.height), (Some(16), Some(8)));
+
+    entity.width = Some(-8);
+    assert_eq!(Entity::try_from(Element::from(entity.clone())).unwrap(), entity);
+}
+
+#[test]
+fn negative_entity_sizes_load_with_a_warning() -> Result<(), Box<dyn std::error::Error>> {
+    use strawberride::{Entity, Level, LoadOptions, LoadWarning, Map};
+
+    let mut level = Level::new("a", 320, 184).unwrap();
+    level.entities.push(Entity { name: "spikesUp".into(), id: 1, width: Some(-8), ..Default::default() });
+    level.entities.push(Entity { name: "spikesUp".into(), id: 2, width: Some(8), ..Default::default() });
+    let map = Map { levels: vec![level], ..Default::default() };
+    let mut bytes = Vec::new();
+    map.clone().store(&mut bytes, true)?;
+
+    let (loaded, warnings) = Map::load_with_warnings(&mut &bytes[..], LoadOptions::default())?;
+    assert_eq!(loaded, map);
+    assert_eq!(warnings, [LoadWarning::NegativeEntitySize { level: "a".into(), name: "spikesUp".into(), id: 1 }]);
+
+    Ok(())
+}
+
+#[test]
+fn level_tile_coordinates() {
+    use strawberride::Level;
+
+    let mut level = Level::new("tiles", 320, 184).unwrap();
+    level.data.position = (640, -184);
+    assert_eq!(level.tile_size(), (40, 23));
+    assert_eq!((level.solids.width(), level.solids.height()), (40, 23));
+
+    assert_eq!(level.pixel_to_tile(640, -184), Some((0, 0)));
+    assert_eq!(level.pixel_to_tile(657, -170), Some((2, 1)));
+    assert_eq!(level.pixel_to_tile(959, -1), Some((39, 22)));
+    assert_eq!(level.pixel_to_tile(639, -184), None);
+    assert_eq!(level.pixel_to_tile(960, -184), None);
+}
+
+#[test]
+fn level_resize_resizes_tilemaps() {
+    use strawberride::Level;
+
+    let mut level = Level::new("resize", 64, 64).unwrap();
+    level.solids[(7, 7)] = '1';
+    assert!(level.resize(128, 32));
+    assert_eq!(level.tile_size(), (16, 4));
+    assert_eq!((level.solids.width(), level.solids.height()), (16, 4));
+    assert_eq!(level.solids.raw_data().len(), 64);
+
+    assert!(level.resize(64, 64));
+    assert_eq!(level.solids[(7, 7)], '0');
+}
+
+#[test]
+fn integers_stored_as_strings() {
+    use std::collections::HashMap;
+    use strawberride::{Element, Filler, Value};
+
+    let el = Element {
+        name: "rect".into(),
+        attributes: HashMap::from([
+            ("x".into(), Value::Integer(8)),
+            ("w".into(), Value::String("16".into())),
+            ("h".into(), Value::String("garbage".into())),
+        ]),
+        children: vec![]
+    };
+    let filler = Filler::try_from(el).unwrap();
+    assert_eq!(filler.position, (8, 0));
+    assert_eq!(filler.size, (16, 0));
+}
+
+#[test]
+fn absurd_level_sizes_are_rejected() {
+    use strawberride::{Element, Level, Tilemap};
+
+    assert!(Level::new("huge", i32::MAX, 184).is_none());
+
+    let mut level = Level::new("normal", 320, 184).unwrap();
+    assert!(!level.resize(320, i32::MAX));
+    assert_eq!(level.tile_size(), (40, 23));
+
+    let mut el = Element::from(level);
+    el.attributes.insert("width".into(), i32::MAX.into());
+    assert!(Level::try_from(el).is_err());
+
+    let tilemap = || Tilemap::<i32>::new(Level::MAX_TILE_DIMENSION + 1, 1).unwrap();
+    let chars = || Tilemap::<char>::new(Level::MAX_TILE_DIMENSION + 1, 1).unwrap();
+    assert!(Level::from_tilemaps("wide", (0, 0), chars(), chars(), tilemap(), tilemap(), tilemap()).is_none());
+}
+
+#[test]
+fn load_options_limit_level_sizes() -> Result<(), Box<dyn std::error::Error>> {
+    use strawberride::{Level, LoadError, LoadOptions, Map};
+
+    let map = Map { levels: vec![Level::new("wide", 320, 184).unwrap()], ..Default::default() };
+    let mut bytes = Vec::new();
+    map.clone().store(&mut bytes, true)?;
+
+    let narrow = LoadOptions { max_tile_dimension: 39, ..Default::default() };
+    assert!(matches!(Map::load_with_options(&mut &bytes[..], narrow), Err(LoadError::InvalidFieldData("width", _))));
+    let exact = LoadOptions { max_tile_dimension: 40, ..Default::default() };
+    assert_eq!(Map::load_with_options(&mut &bytes[..], exact)?, map);
+
+    Ok(())
+}
+
+#[test]
+fn entity_values_follow_schema() {
+    use std::collections::HashMap;
+    use strawberride::{Entity, Level, Value, ValueKind};
+
+    let mut level = Level::new("schema", 64, 64).unwrap();
+    level.entities.push(Entity {
+        name: "spring".into(),
+        values: HashMap::from([
+            ("playerCanUse".to_string(), Value::String("true".into())),
+            ("speed".to_string(), Value::Integer(3)),
+            ("label".to_string(), Value::String("keep".into())),
+        ]),
+        ..Default::default()
+    });
+    level.triggers.push(Entity {
+        name: "spring".into(),
+        values: HashMap::from([("playerCanUse".to_string(), Value::Integer(1))]),
+        ..Default::default()
+    });
+
+    let schema = HashMap::from([
+        ("spring", HashMap::from([
+            ("playerCanUse", ValueKind::Boolean),
+            ("speed", ValueKind::Float),
+            ("label", ValueKind::Integer),
+        ]))
+    ]);
+    level.normalize_entity_values(&schema);
+
+    let values = &level.entities[0].values;
+    assert_eq!(values["playerCanUse"], Value::Boolean(true));
+    assert_eq!(values["speed"], Value::Float(3.0));
+    assert_eq!(values["label"], Value::String("keep".into()));
+    assert_eq!(level.triggers[0].values["playerCanUse"], Value::Boolean(true));
+}
+
+#[test]
+fn level_from_tilemaps() {
+    use strawberride::{Level, Tilemap};
+
+    let chars = || Tilemap::<char>::new(40, 23).unwrap();
+    let ints = || Tilemap::<i32>::new(40, 23).unwrap();
+
+    let mut solids = chars();
+    solids[(1, 2)] = '1';
+    let level = Level::from_tilemaps("generated", (8, 16), chars(), solids, ints(), ints(), ints()).unwrap();
+    assert_eq!(level.data.position, (8, 16));
+    assert_eq!(level.data.size, (320, 184));
+    assert_eq!(level.tile_size(), (40, 23));
+    assert_eq!(level.solids[(1, 2)], '1');
+
+    let mismatched = Tilemap::<i32>::new(40, 22).unwrap();
+    assert!(Level::from_tilemaps("broken", (0, 0), chars(), chars(), ints(), mismatched, ints()).is_none());
+}
+
+#[test]
+fn relative_nodes_round_trip() {
+    use strawberride::Entity;
+
+    let mut entity = Entity {
+        position: (100.0, 50.0),
+        nodes: vec![(120.0, 50.0), (100.0, 26.5)],
+        ..Default::default()
+    };
+    let relative = entity.relative_nodes();
+    assert_eq!(relative, [(20.0, 0.0), (0.0, -23.5)]);
+
+    entity.nodes.clear();
+    entity.set_relative_nodes(relative);
+    assert_eq!(entity.nodes, [(120.0, 50.0), (100.0, 26.5)]);
+}
+
+#[test]
+fn checkpoints_are_detected() {
+    use strawberride::Entity;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    assert!(!level.has_checkpoint());
+
+    for (id, x) in [(20, 200.0), (21, 40.0)] {
+        level.entities.push(common::entity("checkpoint", id, (x, 168.0)));
+    }
+    assert!(level.has_checkpoint());
+    let ids = |entities: Vec<&Entity>| entities.iter().map(|e| e.id).collect::<Vec<_>>();
+    assert_eq!(ids(level.checkpoints()), [20, 21]);
+    assert_eq!(ids(level.checkpoints_by_position()), [21, 20]);
+}
+
+#[test]
+fn tile_size_constant_matches_conversions() {
+    use strawberride::{Level, TILE_SIZE};
+
+    let mut level = Level::new("grid", 10 * TILE_SIZE, 4 * TILE_SIZE).unwrap();
+    level.data.position = (-3 * TILE_SIZE, 0);
+    assert_eq!(level.tile_size(), (10, 4));
+    assert_eq!(level.pixel_to_tile(0, TILE_SIZE), Some((3, 1)));
+    assert_eq!(level.pixel_to_tile(TILE_SIZE - 1, 2 * TILE_SIZE - 1), Some((3, 1)));
+}
+
+#[test]
+fn entities_missing_attributes() {
+    use strawberride::Value;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    // Entities with other names aren't reported, even without the attribute
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    for (id, has_flag) in [(10, true), (11, false), (12, false)] {
+        let mut entity = common::entity("flagDoor", id, (0., 0.));
+        if has_flag {
+            entity.values.insert("flag".into(), Value::String("opened".into()));
+        }
+        level.entities.push(entity);
+    }
+
+    let missing: Vec<_> = level.entities_missing_attr("flagDoor", "flag").iter().map(|e| e.id).collect();
+    assert_eq!(missing, [11, 12]);
+}
+
+#[test]
+fn tilemap_layer_accessors() {
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+
+    for tilemap in level.char_tilemaps_mut() {
+        tilemap[(0, 0)] = '9';
+    }
+    assert_eq!(level.bg[(0, 0)], '9');
+    assert_eq!(level.solids[(0, 0)], '9');
+
+    for tilemap in level.int_tilemaps_mut() {
+        tilemap.map_cells(|_| 4);
+    }
+    assert!(level.int_tilemaps().iter().all(|tilemap| tilemap.raw_data().iter().all(|&id| id == 4)));
+    assert_eq!(level.char_tilemaps()[1].width(), 40);
+}
+
+#[test]
+fn solid_fill_ratios() {
+    use strawberride::Level;
+
+    let mut level = Level::new("fill", 32, 16).unwrap();
+    assert_eq!(level.solid_fill_ratio(), 0.0);
+    level.solids[(0, 0)] = '1';
+    level.solids[(3, 1)] = '3';
+    assert_eq!(level.solids.non_empty_count(), 2);
+    assert_eq!(level.solid_fill_ratio(), 0.25);
+    level.solids.map_cells(|_| '1');
+    assert_eq!(level.solid_fill_ratio(), 1.0);
+
+    assert_eq!(Level::new("empty", 0, 0).unwrap().solid_fill_ratio(), 0.0);
+}
+
+#[test]
+fn oversized_entity_lists_are_flagged() {
+    let mut map = common::map(vec![common::level("a-00", (0, 0), (320, 184))]);
+    assert!(map.levels[0].oversized_entity_lists().is_empty());
+
+    map.levels[0].entities.extend((0 .. 70000).map(|id| common::entity("strawberry", id, (0., 0.))));
+    assert_eq!(map.levels[0].oversized_entity_lists(), ["entities"]);
+    assert_eq!(map.oversized_elements(), ["entities"]);
+    assert!(map.store(&mut Vec::new(), true).is_err());
+}
+
+#[test]
+fn entities_near_a_point() {
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+
+    let ids = |x, y, radius| level.entities_near(x, y, radius).iter().map(|e| e.id).collect::<Vec<_>>();
+    assert_eq!(ids(20.0, 165.0, 5.0), [1]);
+    assert_eq!(ids(40.0, 168.0, 24.0), [1, 2]);
+    assert_eq!(ids(40.0, 168.0, 23.9), Vec::<i32>::new());
+    assert_eq!(ids(64.0, 100.0, 10.0), Vec::<i32>::new());
+}
+
+#[test]
+fn decal_depths() {
+    use strawberride::Decal;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    let decal = |depth| Decal { depth, ..Default::default() };
+    level.bg_decals = vec![decal(9000), decal(-10), decal(9000)];
+    level.fg_decals = vec![decal(-10500), decal(-10), decal(0)];
+    assert_eq!(level.decal_depths().into_iter().collect::<Vec<_>>(), [-10500, -10, 0, 9000]);
+
+    level.bg_decals.clear();
+    level.fg_decals.clear();
+    assert!(level.decal_depths().is_empty());
+}
+
+#[test]
+fn level_flags() {
+    use strawberride::{Map, Value};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    assert_eq!(level.flag("cassetteBlocks"), None);
+    level.set_flag("cassetteBlocks", true);
+    level.extra_data.insert("altRoute".into(), Value::String("False".into()));
+    level.extra_data.insert("notAFlag".into(), Value::String("maybe".into()));
+
+    let loaded = Map::load(&mut &common::store_bytes(common::map(vec![level]))[..], true).unwrap();
+    let level = &loaded.levels[0];
+    assert_eq!(level.flag("cassetteBlocks"), Some(true));
+    assert_eq!(level.flag("altRoute"), Some(false));
+    assert_eq!(level.flag("notAFlag"), None);
+}
+
+#[test]
+fn blank_like_keeps_settings() {
+    use strawberride::Decal;
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.data.underwater = true;
+    level.data.music = "music_oldsite_awake".into();
+    level.set_flag("cassetteBlocks", true);
+    level.solids[(0, 22)] = '1';
+    level.bg[(3, 3)] = '2';
+    level.fg_tiles[(1, 1)] = 12;
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.triggers.push(common::entity("windTrigger", 2, (0., 0.)));
+    level.bg_decals.push(Decal::default());
+    level.fg_decals.push(Decal::default());
+
+    let blank = level.blank_like("a-02");
+    assert_eq!(blank.name, "a-02");
+    assert_eq!(blank.data, level.data);
+    assert_eq!(blank.flag("cassetteBlocks"), Some(true));
+    assert_eq!(blank.tile_size(), level.tile_size());
+
+    assert!(blank.entities.is_empty() && blank.triggers.is_empty());
+    assert!(blank.bg_decals.is_empty() && blank.fg_decals.is_empty());
+    assert_eq!(blank.solids.non_empty_count(), 0);
+    assert_eq!(blank.bg.non_empty_count(), 0);
+    assert!(blank.int_tilemaps().iter().all(|tilemap| tilemap.non_empty_count() == 0));
+    assert_eq!((blank.solids.width(), blank.solids.height()), (40, 23));
+}
+
+#[test]
+fn level_stats() {
+    use strawberride::{Decal, LevelStats};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    for x in 0..40 {
+        level.solids[(x, 22)] = '1';
+    }
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+    level.triggers.push(common::entity("windTrigger", 3, (0., 0.)));
+    level.fg_decals.push(Decal::default());
+
+    let stats = level.stats();
+    // A full row of solids out of 23
+    assert_eq!(stats, LevelStats {
+        entities: 2,
+        triggers: 1,
+        bg_decals: 0,
+        fg_decals: 1,
+        solid_fill_ratio: 40.0 / (40.0 * 23.0),
+        tile_size: (40, 23)
+    });
+    assert_eq!(stats.solid_fill_ratio, level.solid_fill_ratio());
+
+    assert_eq!(strawberride::Level::default().stats(), LevelStats::default());
+}
+
+#[test]
+fn camera_targets() {
+    let mut level = common::level("wide", (0, 0), (960, 360));
+    assert_eq!(level.camera_target((480.0, 180.0)), (320.0, 90.0));
+
+    level.data.camera_offset = (2, -1);
+    assert_eq!(level.camera_target((480.0, 180.0)), (320.0 + 96.0, 90.0 - 32.0));
+
+    // The camera stays inside of the level
+    assert_eq!(level.camera_target((8.0, 350.0)), (0.0, 180.0));
+    level.data.camera_offset = (10, 10);
+    assert_eq!(level.camera_target((480.0, 180.0)), (640.0, 180.0));
+
+    let small = common::level("small", (0, 0), (160, 96));
+    assert_eq!(small.camera_target((80.0, 48.0)), (0.0, 0.0));
+}
+
+#[test]
+fn entities_by_attribute() {
+    use std::collections::HashMap;
+    use strawberride::{Entity, Value};
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    for (id, y, color) in [
+        (10, 160.0, Value::String("ff0000".into())),
+        (11, 40.0, Value::RleString("ff0000".into())),
+        (12, 168.0, Value::String("00ff00".into()))
+    ] {
+        level.entities.push(Entity {
+            values: HashMap::from([("color".into(), color)]),
+            ..common::entity("colorBlock", id, (0.0, y))
+        });
+    }
+
+    let ids = |entities: Vec<&Entity>| entities.iter().map(|entity| entity.id).collect::<Vec<_>>();
+    assert_eq!(ids(level.entities_with_attr("color", &Value::String("ff0000".into()))), [10, 11]);
+    assert_eq!(ids(level.entities_with_attr("color", &Value::Integer(0))), Vec::<i32>::new());
+    assert_eq!(ids(level.entities_where(|entity| entity.position.1 > 100.0)), [10, 12]);
+}
+
+#[test]
+fn decal_flipping() {
+    use strawberride::Decal;
+
+    let mut decal = Decal { scale: (1.5, -2.0), ..Default::default() };
+    assert!(!decal.is_flipped_x() && decal.is_flipped_y());
+
+    decal.flip_x();
+    decal.flip_y();
+    assert_eq!(decal.scale, (-1.5, 2.0));
+    assert!(decal.is_flipped_x() && !decal.is_flipped_y());
+
+    decal.flip_x();
+    decal.flip_y();
+    assert_eq!(decal.scale, (1.5, -2.0));
+}
+
+#[test]
+fn empty_layers() {
+    let mut level = common::level("solids-only", (0, 0), (320, 184));
+    assert_eq!(level.empty_layers(), ["bg", "bgtiles", "fgtiles", "objtiles", "solids"]);
+
+    level.solids[(4, 4)] = '3';
+    assert_eq!(level.empty_layers(), ["bg", "bgtiles", "fgtiles", "objtiles"]);
+
+    level.fg_tiles[(1, 1)] = 12;
+    assert_eq!(level.empty_layers(), ["bg", "bgtiles", "objtiles"]);
+}
+
+#[test]
+fn duplicate_entities() {
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+    level.entities[1].nodes = vec![(72.0, 160.0)];
+    level.triggers.push(common::entity("windTrigger", 3, (0., 0.)));
+
+    // The trigger has the highest id so far
+    let copy = level.duplicate_entity(1, (16.0, -8.5)).unwrap();
+    assert_eq!(copy, 2);
+    let (original, copy) = (&level.entities[1], &level.entities[copy]);
+    assert_eq!(copy.id, 4);
+    assert_eq!(copy.name, original.name);
+    assert_eq!(copy.position, (80.0, 159.5));
+    assert_eq!(copy.nodes, [(88.0, 151.5)]);
+
+    assert_eq!(level.duplicate_entity(0, (0.0, 0.0)), Some(3));
+    assert_eq!(level.entities[3].id, 5);
+    assert_eq!(level.duplicate_entity(10, (0.0, 0.0)), None);
+    assert_eq!(level.entities.len(), 4);
+}
+
+#[test]
+fn decal_position_rounding() {
+    use strawberride::Decal;
+
+    let mut decal = Decal { position: (10.4, -3.5), ..Default::default() };
+    decal.round_position();
+    assert_eq!(decal.position, (10.0, -4.0));
+
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.bg_decals.push(Decal { position: (0.5, 99.99), ..Default::default() });
+    level.fg_decals.push(Decal { position: (40.2, 39.7), ..Default::default() });
+    level.round_all_decal_positions();
+    assert_eq!(level.bg_decals[0].position, (1.0, 100.0));
+    assert_eq!(level.fg_decals[0].position, (40.0, 40.0));
+}
+
+#[test]
+fn entities_csv_export() {
+    let mut first = common::level("a-00", (0, 0), (320, 184));
+    first.entities.push(common::entity("player", 1, (16., 168.)));
+    first.entities.push(strawberride::Entity { width: Some(16), ..common::entity("spikesUp", 2, (64., 168.)) });
+    first.triggers.push(strawberride::Entity { width: Some(32), height: Some(32), ..common::entity("windTrigger", 3, (0., 0.)) });
+    let mut second = common::level("b,\"c\"", (320, 0), (320, 184));
+    second.entities.push(common::entity("refill", 4, (8.5, 0.)));
+    let map = common::map(vec![first, second]);
+
+    assert_eq!(map.entities_to_csv(), concat!(
+        "level,name,id,x,y,width,height\n",
+        "a-00,player,1,16,168,,\n",
+        "a-00,spikesUp,2,64,168,16,\n",
+        "a-00,windTrigger,3,0,0,32,32\n",
+        "\"b,\"\"c\"\"\",refill,4,8.5,0,,\n"
+    ));
+}
+
+#[test]
+fn level_fingerprints() {
+    let mut level = common::level("a-00", (0, 0), (320, 184));
+    level.solids[(0, 22)] = '1';
+    level.entities.push(common::entity("player", 1, (16., 168.)));
+    level.entities.push(common::entity("spikesUp", 2, (64., 168.)));
+
+    // Same room elsewhere, under another name, with its entities renumbered and shuffled
+    let mut copy = level.clone();
+    copy.name = "copy".into();
+    copy.data.position = (1000, -200);
+    copy.entities.reverse();
+    for entity in &mut copy.entities {
+        entity.id += 10;
+    }
+    assert_eq!(copy.fingerprint(), level.fingerprint());
+
+    copy.solids[(5, 5)] = '3';
+    assert_ne!(copy.fingerprint(), level.fingerprint());
+    assert_ne!(common::level("a-01", (0, 0), (320, 184)).fingerprint(), level.fingerprint());
+}
+
+#[test]
+fn level_fingerprints_are_stable() {
+    use strawberride::{Entity, Level, Value};
+
+    let mut level = Level::new("a", 16, 16).unwrap();
+    level.solids[(0, 1)] = '1';
+    level.fg_tiles[(1, 1)] = 7;
+    let mut spikes = Entity { name: "spikesUp".into(), id: 1, position: (8.0, 8.0), width: Some(8), ..Default::default() };
+    spikes.values.insert("type".into(), Value::String("default".into()));
+    level.entities.push(spikes);
+
+    // Fingerprints are meant to be saved, so this must never change
+    assert_eq!(level.fingerprint(), 0x0bca_de84_ba66_c887);
+}
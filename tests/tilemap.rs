@@ -0,0 +1,188 @@
+use strawberride::Tilemap;
+
+#[test]
+fn set_height_updates_height() {
+    let mut map = Tilemap::<char>::new(3, 2).unwrap();
+    map[(1, 1)] = '1';
+
+    assert!(map.set_height(4));
+    assert_eq!(map.height(), 4);
+    assert_eq!(map.raw_data().len(), 12);
+    assert_eq!(map.get(1, 3), Some(&'0'));
+
+    assert!(map.set_height(1));
+    assert_eq!(map.height(), 1);
+    assert_eq!(map.raw_data(), ['0'; 3]);
+    assert_eq!(map.get(1, 1), None);
+}
+
+#[test]
+fn set_width_from_zero_width() {
+    let mut map = Tilemap::<char>::new(0, 2).unwrap();
+
+    assert!(map.set_width(3));
+    assert_eq!(map.width(), 3);
+    assert_eq!(map.raw_data(), ['0'; 6]);
+    assert_eq!(map.get(2, 1), Some(&'0'));
+}
+
+#[test]
+fn map_cells_increments_non_empty() {
+    let mut map = Tilemap::<i32>::new(3, 2).unwrap();
+    map[(0, 0)] = 4;
+    map[(2, 1)] = 0;
+
+    let empty = map.empty();
+    map.map_cells(|id| if id == empty { id } else { id + 1 });
+
+    assert_eq!(map.raw_data(), [5, -1, -1, -1, -1, 1]);
+}
+
+#[test]
+fn signed_access() {
+    let mut map = Tilemap::<char>::new(4, 3).unwrap();
+    map[(2, 1)] = 'a';
+
+    assert_eq!(map.get_signed(-1, 0), None);
+    assert_eq!(map.get_signed(0, -1), None);
+    assert_eq!(map.get_signed(4, 0), None);
+    assert_eq!(map.get_signed(2, 1), map.get(2, 1));
+    assert_eq!(map.get_signed(0, 0), Some(&'0'));
+
+    *map.get_signed_mut(3, 2).unwrap() = 'b';
+    assert_eq!(map[(3, 2)], 'b');
+    assert!(map.get_signed_mut(-3, 2).is_none());
+}
+
+#[test]
+fn insert_and_remove_rows_and_columns() {
+    let mut tilemap = Tilemap::<i32>::new(3, 3).unwrap();
+    for (i, cell) in (0 ..).zip([(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)]) {
+        tilemap[cell] = i;
+    }
+
+    assert!(tilemap.insert_row(1));
+    assert_eq!(tilemap.height(), 4);
+    assert_eq!(tilemap.raw_data(), &[0, 1, 2, -1, -1, -1, 3, 4, 5, 6, 7, 8]);
+    assert!(tilemap.remove_row(1));
+    assert_eq!(tilemap.raw_data(), &[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+    assert!(tilemap.insert_col(3));
+    assert_eq!(tilemap.width(), 4);
+    assert_eq!(tilemap.raw_data(), &[0, 1, 2, -1, 3, 4, 5, -1, 6, 7, 8, -1]);
+    assert!(tilemap.remove_col(0));
+    assert_eq!(tilemap.raw_data(), &[1, 2, -1, 4, 5, -1, 7, 8, -1]);
+    assert!(tilemap.insert_col(1));
+    assert_eq!(tilemap.raw_data(), &[1, -1, 2, -1, 4, -1, 5, -1, 7, -1, 8, -1]);
+    assert!(tilemap.remove_col(1));
+
+    assert!(!tilemap.insert_row(4));
+    assert!(!tilemap.remove_col(3));
+    assert_eq!((tilemap.width(), tilemap.height()), (3, 3));
+}
+
+#[test]
+fn isolated_tiles_are_found() {
+    let mut tilemap = Tilemap::<char>::new(8, 6).unwrap();
+    // A lone tile
+    tilemap[(2, 2)] = '1';
+    // A connected cluster
+    tilemap[(5, 3)] = '1';
+    tilemap[(6, 3)] = '1';
+    tilemap[(6, 4)] = '3';
+    // Touching the edge
+    tilemap[(0, 5)] = '1';
+
+    assert_eq!(tilemap.isolated_tiles(), [(2, 2)]);
+}
+
+#[test]
+fn region_equality() {
+    let a = Tilemap::<i32>::new(10, 8).unwrap();
+    let mut b = a.clone();
+    b[(1, 1)] = 5;
+
+    assert_eq!(a.region_eq(&b, 2, 2, 8, 6), Some(true));
+    assert_eq!(a.region_eq(&b, 0, 0, 3, 3), Some(false));
+    assert_eq!(a.region_eq(&b, 1, 1, 1, 1), Some(false));
+    assert_eq!(a.region_eq(&b, 0, 0, 0, 0), Some(true));
+
+    assert_eq!(a.region_eq(&b, 5, 5, 6, 1), None);
+    assert_eq!(a.region_eq(&b, 0, 0, 1, usize::MAX), None);
+    let small = Tilemap::<i32>::new(4, 4).unwrap();
+    assert_eq!(a.region_eq(&small, 2, 2, 3, 1), None);
+    assert_eq!(a.region_eq(&small, 0, 0, 4, 4), Some(true));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_map_cells_matches_sequential() {
+    let mut sequential = Tilemap::<i32>::new(300, 200).unwrap();
+    for (i, cell) in (0 ..).zip(unsafe { sequential.raw_data_mut() }) {
+        *cell = i % 17 - 1;
+    }
+    let mut parallel = sequential.clone();
+
+    let f = |cell: i32| if cell < 0 { 4 } else { cell * 3 };
+    sequential.map_cells(f);
+    parallel.par_map_cells(f);
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn empty_tilemaps() {
+    let mut tilemap = Tilemap::<i32>::new(4, 3).unwrap();
+    assert!(tilemap.is_empty());
+    tilemap[(3, 2)] = 0;
+    assert!(!tilemap.is_empty());
+    assert!(Tilemap::<char>::new(0, 0).unwrap().is_empty());
+}
+
+#[test]
+fn collision_grids() {
+    let mut tilemap = Tilemap::<char>::new(3, 2).unwrap();
+    tilemap[(0, 0)] = '1';
+    tilemap[(2, 1)] = 'g';
+    assert_eq!(tilemap.collision_grid(), [true, false, false, false, false, true]);
+
+    tilemap.set_empty('1');
+    assert_eq!(tilemap.collision_grid(), [false, true, true, true, true, true]);
+}
+
+#[test]
+fn reachability() {
+    // Two open areas split by a vertical wall, with a ledge jutting out into the right one
+    let mut tilemap = Tilemap::<char>::new(5, 4).unwrap();
+    for y in 0..4 {
+        tilemap[(2, y)] = '1';
+    }
+    tilemap[(3, 2)] = '1';
+    tilemap[(4, 2)] = '1';
+
+    assert!(tilemap.reachable((0, 0), (1, 3)));
+    assert!(tilemap.reachable((3, 0), (4, 1)));
+    assert!(tilemap.reachable((0, 0), (0, 0)));
+    assert!(!tilemap.reachable((0, 0), (3, 0)));
+    assert!(!tilemap.reachable((3, 0), (3, 3)));
+    // Solid and out of bounds cells can't be reached
+    assert!(!tilemap.reachable((0, 0), (2, 0)));
+    assert!(!tilemap.reachable((0, 0), (5, 0)));
+}
+
+#[test]
+fn tile_runs() {
+    let mut tilemap = Tilemap::<char>::new(5, 3).unwrap();
+    // AAB00
+    tilemap[(0, 0)] = 'A';
+    tilemap[(1, 0)] = 'A';
+    tilemap[(2, 0)] = 'B';
+    // 00000 has no runs, and 0A0A0 keeps the empty cells between tiles
+    tilemap[(1, 2)] = 'A';
+    tilemap[(3, 2)] = 'A';
+
+    assert_eq!(tilemap.runs().take(2).collect::<Vec<_>>(), [('A', 2), ('B', 1)]);
+    assert_eq!(tilemap.runs().collect::<Vec<_>>(), [
+        ('A', 2), ('B', 1),
+        ('0', 1), ('A', 1), ('0', 1), ('A', 1)
+    ]);
+}